@@ -7,11 +7,70 @@
 //! Cargo re-run the build script whenever `memory.x` is changed,
 //! updating `memory.x` ensures a rebuild of the application with the
 //! new memory settings.
+//!
+//! It also turns the PNGs in `assets/` into the 1-bit `Sprite` statics used by the game, see
+//! `SPRITES`, and checks `assets/levels.toml` and turns it into the `LEVELS` table. The
+//! generated code is included by `src/objects.rs`.
 
 use std::env;
-use std::fs::File;
+use std::fmt::Write as _;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+/// a sprite generated from a PNG
+struct SpriteAsset {
+    name: &'static str,
+    file: &'static str,
+    width: u32,
+    height: u32,
+    /// art is drawn dark on light, instead of light on dark
+    invert: bool,
+}
+
+const fn asset(name: &'static str, file: &'static str, width: u32, height: u32, invert: bool) -> SpriteAsset {
+    SpriteAsset { name, file, width, height, invert }
+}
+
+// size is fixed here, because hit boxes and layout depend on it. a pixel is on when it is
+// bright and opaque.
+const SPRITES: &[SpriteAsset] = &[
+    asset("PLAYER_1_SPRITE", "Player.png", 11, 9, true),
+    asset("PLAYER_2_SPRITE", "spaceship_2.png", 11, 10, false),
+    asset("ENEMY_SPRITE", "enemy.png", 11, 10, false),
+    asset("ENEMY_SPRITE_2", "enemy2.png", 11, 10, false),
+    asset("BOSS_SPRITE", "boss.png", 30, 25, false),
+    asset("BULLET_SPRITE", "bullet3*3.png", 3, 3, false),
+    asset("ASTEROID_SPRITE_1", "asteroid1.png", 8, 8, false),
+    asset("ASTEROID_SPRITE_2", "asteroid2.png", 8, 8, false),
+    asset("ASTEROID_SPRITE_3", "asteroid3.png", 8, 8, false),
+    asset("ASTEROID_SPRITE_4", "asteroid4.png", 8, 8, false),
+    // asteroids other than the medium ones
+    asset("LARGE_ASTEROID_SPRITE_1", "large_asteroid1.png", 12, 12, false),
+    asset("LARGE_ASTEROID_SPRITE_2", "large_asteroid2.png", 12, 12, false),
+    asset("SMALL_ASTEROID_SPRITE_1", "small_asteroid1.png", 4, 4, false),
+    asset("SMALL_ASTEROID_SPRITE_2", "small_asteroid2.png", 4, 4, false),
+    asset("ENGINE_SPRITE_1", "engine1.png", 3, 1, false),
+    asset("ENGINE_SPRITE_2", "engine2.png", 3, 1, false),
+    // power-ups, a letter in a box
+    asset("RAPID_FIRE_ICON", "rapid_fire_icon.png", 7, 7, false),
+    asset("TRIPLE_SHOT_ICON", "triple_shot_icon.png", 7, 7, false),
+    asset("SHIELD_ICON", "shield_icon.png", 7, 7, false),
+    asset("EXTRA_LIFE_ICON", "extra_life_icon.png", 7, 7, false),
+    asset("BOMB_ICON", "bomb_icon.png", 7, 7, false),
+    asset("SPREAD_ICON", "spread_icon.png", 7, 7, false),
+    asset("LASER_ICON", "laser_icon.png", 7, 7, false),
+    asset("PIERCING_ICON", "piercing_icon.png", 7, 7, false),
+    asset("HOMING_ICON", "homing_icon.png", 7, 7, false),
+    // bullets of weapons other than the single shot
+    asset("LASER_SPRITE", "laser_beam.png", 1, 8, false),
+    asset("PIERCING_SPRITE", "piercing_shot.png", 2, 4, false),
+    asset("MISSILE_SPRITE", "missile.png", 3, 5, false),
+];
 
 fn main() {
     // Put `memory.x` in our output directory and ensure it's
@@ -28,4 +87,323 @@ fn main() {
     // here, we ensure the build script is only re-run when
     // `memory.x` is changed.
     println!("cargo:rerun-if-changed=memory.x");
+    println!("cargo:rerun-if-changed=src/limits.rs");
+
+    let assets = Path::new(&env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("assets");
+    let mut code = String::from("// generated by build.rs from the PNGs in assets/, do not edit\n");
+    for sprite in SPRITES {
+        let path = assets.join(sprite.file);
+        println!("cargo:rerun-if-changed={}", path.display());
+        match sprite_code(sprite, &path) {
+            Ok(sprite_code) => code.push_str(&sprite_code),
+            Err(err) => {
+                eprintln!("error: sprite {} from {}: {}", sprite.name, path.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+    fs::write(out.join("sprites.rs"), code).unwrap();
+
+    let levels = assets.join("levels.toml");
+    println!("cargo:rerun-if-changed={}", levels.display());
+    match levels_code(&levels, "assets/levels.toml") {
+        Ok(code) => fs::write(out.join("levels.rs"), code).unwrap(),
+        Err(errors) => {
+            for err in errors {
+                eprintln!("error: {}", err);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+/// decodes the PNG and returns the `Sprite` static for it
+fn sprite_code(sprite: &SpriteAsset, path: &Path) -> Result<String, String> {
+    let (width, height, pixels) = load_png(path)?;
+    if width == 0 || height == 0 || width > u8::MAX as u32 || height > u8::MAX as u32 {
+        return Err(format!("image is {}x{}, sprites must be between 1x1 and 255x255", width, height));
+    }
+    if (width, height) != (sprite.width, sprite.height) {
+        return Err(format!(
+            "image is {}x{}, expected {}x{}",
+            width, height, sprite.width, sprite.height
+        ));
+    }
+    let data = pack(width, height, &pixels, sprite.invert);
+    let mut code = format!("pub static {} : Sprite = Sprite{{\n    data    : &[", sprite.name);
+    for (index, byte) in data.iter().enumerate() {
+        if index % 16 == 0 {
+            code.push_str("\n        ");
+        }
+        write!(code, "0x{:02x}, ", byte).unwrap();
+    }
+    write!(code, "\n    ],\n    width   : {},\n    height  : {},\n}};\n", width, height).unwrap();
+    Ok(code)
+}
+
+/// width, height and `(luma, alpha)` of every pixel of a decoded PNG
+type Pixels = (u32, u32, Vec<(u8, u8)>);
+
+/// decodes the PNG into 8 bit `(luma, alpha)` pairs, row by row
+fn load_png(path: &Path) -> Result<Pixels, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf).map_err(|err| err.to_string())?;
+
+    let channels = info.color_type.samples();
+    let mut pixels = Vec::with_capacity((info.width * info.height) as usize);
+    for row in buf.chunks(info.line_size).take(info.height as usize) {
+        for pixel in row.chunks(channels).take(info.width as usize) {
+            let (luma, alpha) = match info.color_type {
+                png::ColorType::Grayscale => (pixel[0], 255),
+                png::ColorType::GrayscaleAlpha => (pixel[0], pixel[1]),
+                png::ColorType::RGB => (luma(pixel), 255),
+                png::ColorType::RGBA => (luma(pixel), pixel[3]),
+                // palette is expanded by the decoder
+                png::ColorType::Indexed => return Err("unexpected indexed color".into()),
+            };
+            pixels.push((luma, alpha));
+        }
+    }
+    Ok((info.width, info.height, pixels))
+}
+
+fn luma(rgb: &[u8]) -> u8 {
+    ((rgb[0] as u32 * 299 + rgb[1] as u32 * 587 + rgb[2] as u32 * 114) / 1000) as u8
+}
+
+/// thresholds to 1 bit and packs the rows MSB first, every row padded to a whole byte, the way
+/// `ImageRaw<BinaryColor>` expects it
+fn pack(width: u32, height: u32, pixels: &[(u8, u8)], invert: bool) -> Vec<u8> {
+    let stride = ((width + 7) / 8) as usize;
+    let mut data = vec![0u8; stride * height as usize];
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let (luma, alpha) = pixels[y * width as usize + x];
+            if alpha >= 128 && (luma >= 128) != invert {
+                data[y * stride + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+    data
+}
+
+// enemy kinds levels can use, with their constant in `src/objects.rs`
+const KINDS: &[(&str, &str)] = &[
+    ("hoverer", "HOVERER"),
+    ("strafer", "STRAFER"),
+    ("swayer", "SWAYER"),
+    ("diver", "DIVER"),
+    ("formation", "FORMATION"),
+    ("tank", "TANK"),
+    ("gunship", "GUNSHIP"),
+];
+// `move` and `fire` overrides, with their `Behaviour` and `FirePattern` variant
+const MOVES: &[(&str, &str)] = &[
+    ("hover", "Hover"),
+    ("strafe", "Strafe"),
+    ("sine_wave", "SineWave"),
+    ("dive_bomb", "DiveBomb"),
+    ("formation", "Formation"),
+];
+const FIRES: &[(&str, &str)] = &[
+    ("straight", "Straight"),
+    ("aimed", "Aimed"),
+    ("spread", "Spread"),
+    ("burst", "Burst"),
+    ("alternating", "Alternating"),
+];
+// limits of the game, shared with the crate
+include!("src/limits.rs");
+// ticks between enemies of a wave, unless they have a delay
+const DEFAULT_DELAY: i64 = 20;
+// most hits an enemy can be made to take
+const MAX_ENEMY_HEALTH: i64 = 20;
+// percent of kills dropping a power-up, unless the level says otherwise
+const DEFAULT_POWER_UP_CHANCE: i64 = 15;
+// ticks for a player to get a round back, unless the level says otherwise
+const DEFAULT_AMMO_REFILL: i64 = 30;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelsFile {
+    level: Vec<LevelDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelDef {
+    asteroids: Spanned<i64>,
+    asteroid_speed: Spanned<f64>,
+    enemy_cool_down: Spanned<i64>,
+    power_up_chance: Option<Spanned<i64>>,
+    ammo_refill: Option<Spanned<i64>>,
+    #[serde(default)]
+    boss: bool,
+    #[serde(default)]
+    wave: Vec<WaveDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaveDef {
+    enemies: Vec<EnemyDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnemyDef {
+    kind: Spanned<String>,
+    #[serde(rename = "move")]
+    movement: Option<Spanned<String>>,
+    fire: Option<Spanned<String>>,
+    health: Option<Spanned<i64>>,
+    x: Option<Spanned<i64>>,
+    y: Option<Spanned<i64>>,
+    delay: Option<Spanned<i64>>,
+}
+
+/// collects errors with the line they are on
+struct Checker<'a> {
+    // as shown in errors
+    name: &'a str,
+    source: &'a str,
+    errors: Vec<String>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, offset: usize, message: String) {
+        let line = self.source[..offset.min(self.source.len())].matches('\n').count() + 1;
+        self.errors.push(format!("{}:{}: {}", self.name, line, message));
+    }
+
+    fn int(&mut self, value: &Spanned<i64>, name: &str, min: i64, max: i64) -> i64 {
+        let int = *value.get_ref();
+        if int < min || int > max {
+            self.error(value.start(), format!("{} is {}, must be between {} and {}", name, int, min, max));
+        }
+        int.max(min).min(max)
+    }
+
+    /// rust name of given name in table
+    fn lookup(&mut self, table: &[(&str, &'static str)], value: &Spanned<String>, name: &str) -> &'static str {
+        match table.iter().find(|(key, _)| *key == value.get_ref().as_str()) {
+            Some(&(_, rust)) => rust,
+            None => {
+                let names: Vec<&str> = table.iter().map(|(key, _)| *key).collect();
+                self.error(value.start(), format!(
+                    "unknown {} \"{}\", expected one of {}",
+                    name, value.get_ref(), names.join(", ")
+                ));
+                table[0].1
+            }
+        }
+    }
+}
+
+/// checks the levels and returns the `LEVELS` static for them, or every error found
+fn levels_code(path: &Path, name: &str) -> Result<String, Vec<String>> {
+    let source = fs::read_to_string(path).map_err(|err| vec![format!("{}: {}", name, err)])?;
+    // syntax and type errors come with line and column from the parser
+    let file: LevelsFile = toml::from_str(&source).map_err(|err| vec![format!("{}: {}", name, err)])?;
+    let mut checker = Checker { name, source: &source, errors: Vec::new() };
+    if file.level.is_empty() {
+        checker.error(0, "no levels, add at least one [[level]]".into());
+    }
+
+    let mut code = String::from("// generated by build.rs from assets/levels.toml, do not edit\n");
+    writeln!(code, "pub static LEVELS: [Level; {}] = [", file.level.len()).unwrap();
+    for level in &file.level {
+        let asteroids = checker.int(&level.asteroids, "asteroids", 0, MAX_ASTEROIDS as i64);
+        let cool_down = checker.int(&level.enemy_cool_down, "enemy_cool_down", 1, u16::MAX as i64);
+        let power_up_chance = level.power_up_chance.as_ref()
+            .map_or(DEFAULT_POWER_UP_CHANCE, |chance| checker.int(chance, "power_up_chance", 0, 100));
+        let ammo_refill = level.ammo_refill.as_ref()
+            .map_or(DEFAULT_AMMO_REFILL, |refill| checker.int(refill, "ammo_refill", 0, u16::MAX as i64));
+        // sixteenths of a pixel, see `Fx`
+        let speed = (level.asteroid_speed.get_ref() * 16.0).round() as i64;
+        if !(1..=8 * 16).contains(&speed) {
+            checker.error(level.asteroid_speed.start(), format!(
+                "asteroid_speed is {}, must be between 0.0625 and 8 pixels a tick",
+                level.asteroid_speed.get_ref()
+            ));
+        }
+        if level.wave.is_empty() && !level.boss {
+            checker.error(level.asteroids.start(), "level has neither waves nor a boss".into());
+        }
+
+        code.push_str("    Level{\n        waves: &[\n");
+        for wave in &level.wave {
+            code.push_str("            Wave{ enemies: &[\n");
+            let mut size = 0;
+            for enemy in &wave.enemies {
+                let base = checker.lookup(KINDS, &enemy.kind, "enemy kind");
+                let movement = enemy.movement.as_ref().map(|movement| checker.lookup(MOVES, movement, "move"));
+                let fire = enemy.fire.as_ref().map(|fire| checker.lookup(FIRES, fire, "fire"));
+                let formation = movement.map_or(base == "FORMATION", |movement| movement == "Formation");
+                size += if formation { FORMATION_SIZE } else { 1 };
+                let health = enemy.health.as_ref().map(|health| checker.int(health, "health", 1, MAX_ENEMY_HEALTH));
+                // overrides on top of the kind
+                let mut fields = Vec::new();
+                if let Some(movement) = movement {
+                    fields.push(format!("behaviour: Behaviour::{}", movement));
+                }
+                if let Some(fire) = fire {
+                    fields.push(format!("fire: FirePattern::{}", fire));
+                }
+                if let Some(health) = health {
+                    fields.push(format!("health: {}", health));
+                }
+                let kind = if fields.is_empty() {
+                    base.to_string()
+                } else {
+                    format!("EnemyKind{{ {}, ..{} }}", fields.join(", "), base)
+                };
+
+                // formations spread over the whole row, so only their row can be picked
+                let x = enemy.x.as_ref().map(|x| checker.int(x, "x", 1, MAX_ENEMY_X as i64));
+                let y = enemy.y.as_ref().map(|y| checker.int(y, "y", 1, MAX_ENEMY_Y as i64));
+                let pos = match (x, y, formation) {
+                    (None, None, _) => "None".to_string(),
+                    (Some(_), _, true) => {
+                        checker.error(enemy.kind.start(), "formations spread over the whole row, only y can be set".into());
+                        "None".to_string()
+                    }
+                    (None, Some(y), true) => format!("Some((0, {}))", y),
+                    (Some(x), Some(y), false) => format!("Some(({}, {}))", x, y),
+                    _ => {
+                        checker.error(enemy.kind.start(), "x and y have to be set together".into());
+                        "None".to_string()
+                    }
+                };
+                let delay = enemy.delay.as_ref().map_or(DEFAULT_DELAY, |delay| checker.int(delay, "delay", 0, u16::MAX as i64));
+                writeln!(code, "                WaveEnemy{{ kind: {}, pos: {}, delay: {} }},", kind, pos, delay).unwrap();
+            }
+            match wave.enemies.first() {
+                None => checker.error(level.asteroids.start(), "wave without enemies".into()),
+                Some(first) if size > MAX_WAVE_ENEMIES => checker.error(first.kind.start(), format!(
+                    "wave has {} enemies, at most {} fit on screen, formations count {}",
+                    size, MAX_WAVE_ENEMIES, FORMATION_SIZE
+                )),
+                Some(_) => {}
+            }
+            code.push_str("            ] },\n");
+        }
+        writeln!(
+            code,
+            "        ],\n        asteroids: {}, asteroid_speed: Fx::from_raw({}), enemy_cool_down: {}, power_up_chance: {}, ammo_refill: {}, boss: {},\n    }},",
+            asteroids, speed, cool_down, power_up_chance, ammo_refill, level.boss
+        )
+        .unwrap();
+    }
+    code.push_str("];\n");
+
+    if checker.errors.is_empty() {
+        Ok(code)
+    } else {
+        Err(checker.errors)
+    }
 }
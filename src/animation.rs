@@ -0,0 +1,144 @@
+// Frame animations. a sprite sheet is the list of frames, an animation plays it back one tick at
+// a time, so that the speed of an animation doesn't depend on anything else the object does.
+use crate::game::Sprite;
+
+use embedded_graphics::{
+    image::ImageRaw,
+    pixelcolor::BinaryColor,
+};
+
+/// Frames of an animation, all of them have the same size.
+#[derive(Debug)]
+pub struct SpriteSheet{
+    pub frames: &'static [&'static Sprite],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode{
+    /// starts over after the last frame
+    Loop,
+    /// stops after the last frame, see `Animation::is_finished`
+    Once,
+}
+
+/// Playback state of a sprite sheet, owned by the object it animates and advanced with `tick`
+/// once per frame.
+#[derive(Debug, Clone, Copy)]
+pub struct Animation{
+    sheet: &'static SpriteSheet,
+    // ticks every frame is shown for
+    frame_ticks: u8,
+    mode: Mode,
+    frame: u8,
+    ticks: u8,
+}
+
+impl SpriteSheet{
+    pub fn len(&self)->usize{
+        self.frames.len()
+    }
+    pub fn is_empty(&self)->bool{
+        self.frames.is_empty()
+    }
+    pub fn width(&self)->u8{
+        self.frames[0].width()
+    }
+    pub fn height(&self)->u8{
+        self.frames[0].height()
+    }
+}
+
+impl Animation{
+    pub fn new(sheet:&'static SpriteSheet, frame_ticks:u8, mode:Mode)->Self{
+        Self{ sheet, frame_ticks: frame_ticks.max(1), mode, frame:0, ticks:0 }
+    }
+    /// starts at given frame instead of the first one, so that objects spawned together
+    /// don't animate in sync
+    pub fn starting_at(mut self, frame:usize)->Self{
+        self.frame = (frame % self.sheet.len()) as u8;
+        self
+    }
+    /// advances the animation by one frame of the game
+    pub fn tick(&mut self){
+        if self.is_finished(){
+            return;
+        }
+        self.ticks += 1;
+        if self.ticks < self.frame_ticks{
+            return;
+        }
+        self.ticks = 0;
+        self.frame += 1;
+        if self.mode == Mode::Loop && self.frame as usize >= self.sheet.len(){
+            self.frame = 0;
+        }
+    }
+    /// plays the animation again from the first frame
+    pub fn restart(&mut self){
+        self.frame = 0;
+        self.ticks = 0;
+    }
+    /// true once a `Mode::Once` animation has shown its last frame, loops never finish
+    pub fn is_finished(&self)->bool{
+        self.frame as usize >= self.sheet.len()
+    }
+    /// index of the frame shown, last one once finished
+    pub fn frame(&self)->usize{
+        (self.frame as usize).min(self.sheet.len() - 1)
+    }
+    pub fn sprite(&self)->&'static Sprite{
+        self.sheet.frames[self.frame()]
+    }
+    pub fn image(&self)->ImageRaw<'static, BinaryColor>{
+        let sprite = self.sprite();
+        ImageRaw::new(sprite.data, sprite.width() as u32, sprite.height() as u32)
+    }
+    pub fn width(&self)->u8{
+        self.sheet.width()
+    }
+    pub fn height(&self)->u8{
+        self.sheet.height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::*;
+
+    #[test]
+    fn loop_wraps_around(){
+        let mut animation = Animation::new(&ASTEROID_SHEET, 2, Mode::Loop);
+        let mut frames = [0; 10];
+        for frame in frames.iter_mut(){
+            *frame = animation.frame();
+            animation.tick();
+        }
+        assert_eq!(frames, [0, 0, 1, 1, 2, 2, 3, 3, 0, 0]);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn once_stops_on_last_frame(){
+        let mut animation = Animation::new(&EXPLOSION_SHEET, 1, Mode::Once);
+        for _ in 0..EXPLOSION_SHEET.len() - 1{
+            animation.tick();
+            assert!(!animation.is_finished());
+        }
+        animation.tick();
+        assert!(animation.is_finished());
+        animation.tick();
+        assert_eq!(animation.frame(), EXPLOSION_SHEET.len() - 1);
+
+        animation.restart();
+        assert!(!animation.is_finished());
+        assert_eq!(animation.frame(), 0);
+    }
+
+    #[test]
+    fn starting_frame_wraps(){
+        let animation = Animation::new(&ASTEROID_SHEET, 1, Mode::Loop).starting_at(6);
+        assert_eq!(animation.frame(), 2);
+        assert!(core::ptr::eq(animation.sprite(), &ASTEROID_SPRITE_3));
+    }
+}
@@ -6,12 +6,22 @@
 // PF2  : Left
 // PF8  : Shoot
 // PF9  : Rigt
+// PF6  : Pause
+// PF3  : Shoot, second player
+// PF4  : Left, second player
+// PF5  : Right, second player
 
 use space_war as _;
 
 use space_war::{
     types::*,
-    GamePool,
+    board::{DwtClock, DwtCycles, HardwareRng},
+    input::{Input, InputFrame},
+    objects::TICK_RATE,
+    profiler::{Phase, Probe, Profiler},
+    replay::Recording,
+    scene::{Game, Scene},
+    timing::{Clock, FixedStep, Step},
 };
 
 use rtic::app;
@@ -20,11 +30,9 @@ use stm32f7xx_hal as _;
 use stm32f7xx_hal::{
     prelude::*,
     i2c::{BlockingI2c, self},
-    delay::Delay,
     gpio::{Edge, ExtiPin},
     timer::{Timer, Event},
     pac::{EXTI, TIM2},
-    rng::Rng,
 };
 
 use ssd1306::{
@@ -39,14 +47,20 @@ mod app {
     #[resources]
     struct Resources {
         disp : Display,
-        game : GamePool,
-        delay: Delay,
+        game : Game,
         direct:(Left, Right), // direction
         shoot: ButtonShoot,
+        direct_2:(Left2, Right2),
+        shoot_2: ButtonShoot2,
         exti : EXTI,
         timer2: Timer<TIM2>,
-        rng: Rng,
+        clock: DwtClock,
+        rng: HardwareRng,
         pause: Pause,
+        // set by button interrupts, consumed by next frame
+        shoot_pressed: bool,
+        shoot_2_pressed: bool,
+        pause_pressed: bool,
     }
     #[init]
     fn init(c : init::Context)->init::LateResources {
@@ -65,21 +79,29 @@ mod app {
         shoot.trigger_on_edge(&mut exti, Edge::FALLING);
         shoot.enable_interrupt(&mut exti);
 
+        // second player
+        let left_2 = gpiof.pf4.into_pull_up_input();
+        let right_2 = gpiof.pf5.into_pull_up_input();
+        let mut shoot_2 = gpiof.pf3.into_pull_up_input();
+        shoot_2.make_interrupt_source(&mut syscfg, &mut rcc);
+        shoot_2.trigger_on_edge(&mut exti, Edge::FALLING);
+        shoot_2.enable_interrupt(&mut exti);
+
         // pause button
         let mut pause = gpiof.pf6.into_pull_up_input();
         pause.make_interrupt_source(&mut syscfg, &mut rcc);
         pause.trigger_on_edge(&mut exti, Edge::FALLING);
-        // NOTE: pause currently disabled
-        // pause.enable_interrupt(&mut exti);
+        pause.enable_interrupt(&mut exti);
 
-        let rng = c.device.RNG.init();
+        // every game runs on software rng seeded from this, so that it can be reproduced
+        // in the simulator from its replay
+        let rng = HardwareRng::new(c.device.RNG.init());
         let mut rcc = rcc.constrain();
         // if clock is changed need to change timer delay too,
         let clk = rcc.cfgr.sysclk(32.mhz()).freeze();
-        let syst = c.core.SYST;
-
-        // delay object so that game is not too fast
-        let delay = Delay::new(syst, clk);
+        let mut dcb = c.core.DCB;
+        let mut dwt = c.core.DWT;
+        let clock = DwtClock::new(&mut dcb, &mut dwt, &clk);
 
         // initilize Display with i2c
         let i2c_display = BlockingI2c::i2c2(c.device.I2C2, (scl, sda), i2c::Mode::FastPlus{ frequency: 400_000.hz() }, clk, &mut rcc.apb1, 999);
@@ -88,129 +110,143 @@ mod app {
         disp.init().expect("couldn't initiate display");
         disp.set_rotation(DisplayRotation::Rotate270).unwrap();
 
-        // initialize timer for player ammunition
-        let mut fps_timer = Timer::tim2(c.device.TIM2, 1.hz(), clk, &mut rcc.apb1 );
-        fps_timer.listen(Event::TimeOut);
+        // wakes the main loop from WFI, twice per tick so that a tick is never late by more
+        // than half a tick
+        let mut tick_timer = Timer::tim2(c.device.TIM2, (TICK_RATE * 2).hz(), clk, &mut rcc.apb1 );
+        tick_timer.listen(Event::TimeOut);
 
         // set log level
-        let game = GamePool::init(&disp);
-        init::LateResources{ disp, game, delay, direct:(left, right), 
-            shoot, exti, timer2:fps_timer, rng, pause,
+        let game = Game::init(&disp);
+        init::LateResources{ disp, game, direct:(left, right), 
+            shoot, direct_2:(left_2, right_2), shoot_2, exti, timer2:tick_timer, clock, rng, pause,
+            shoot_pressed: false, shoot_2_pressed: false, pause_pressed: false,
         }
     }
 
-    #[idle(resources = [rng,disp, game, delay, &direct])]
+    #[idle(resources = [rng,disp, game, clock, &direct, &direct_2, shoot_pressed, shoot_2_pressed, pause_pressed])]
     fn idle( c: idle::Context)->!{
         // it is the border of display
         let direct = c.resources.direct;
+        let direct_2 = c.resources.direct_2;
         let mut game = c.resources.game;
         let mut display = c.resources.disp;
         let mut rng = c.resources.rng;
+        let mut clock = c.resources.clock;
+        let mut shoot_pressed = c.resources.shoot_pressed;
+        let mut shoot_2_pressed = c.resources.shoot_2_pressed;
+        let mut pause_pressed = c.resources.pause_pressed;
+        let mut step = FixedStep::new(clock.lock(|clock| clock.now_us()));
+        let mut profiler = Profiler::new(DwtCycles);
         loop{
-            game.lock(|game|{
-                rng.lock(|rng|{
-                    game.spawn(rng);
-                });
-                game.update(&direct);
-                game.collect();
-                display.lock(|display:&mut Display|{
-                    display.clear();
-                    game.draw(display);
-                    game.draw_stats(display);
-                    display.flush().unwrap();
-                });
-                if game.is_ok(){
-                    game_over::spawn().unwrap();
-                };
-            });
+            let now = clock.lock(|clock| clock.now_us());
+            match step.poll(now){
+                Step::Tick => {
+                    // sample buttons once per tick
+                    let shoot = shoot_pressed.lock(|pressed|{
+                        core::mem::replace(pressed, false)
+                    });
+                    let shoot_2 = shoot_2_pressed.lock(|pressed|{
+                        core::mem::replace(pressed, false)
+                    });
+                    let mut pause = pause_pressed.lock(|pressed|{
+                        core::mem::replace(pressed, false)
+                    });
+                    // pause while holding left and right toggles the profiler overlay
+                    if pause && direct.left() && direct.right(){
+                        profiler.toggle_overlay();
+                        pause = false;
+                    }
+                    let input = InputFrame::new(direct.left(), direct.right(), shoot, pause)
+                        .with_player_2(direct_2.left(), direct_2.right(), shoot_2);
+                    game.lock(|game|{
+                        let scene = game.scene();
+                        rng.lock(|rng|{
+                            game.tick_probed(input, rng, &mut profiler);
+                        });
+                        if scene == Scene::Playing && game.scene() == Scene::GameOver{
+                            dump_replay(game.recording());
+                        }
+                    });
+                },
+                Step::Draw => {
+                    game.lock(|game|{
+                        game.pool_mut().set_fps(step.fps());
+                        display.lock(|display:&mut Display|{
+                            display.clear();
+                            profiler.measure(Phase::Draw, || game.draw(display)).unwrap();
+                            if profiler.overlay(){
+                                profiler.draw_overlay(display).unwrap();
+                            }
+                            profiler.measure(Phase::Flush, || display.flush()).unwrap();
+                        });
+                    });
+                    if profiler.end_frame(){
+                        profiler.report();
+                    }
+                },
+                // tick timer wakes us up
+                Step::Idle => cortex_m::asm::wfi(),
+            }
         }
     }
 
-    #[task(binds = EXTI2, resources = [shoot, game, exti, timer2], priority = 2)]
+    #[task(binds = EXTI2, resources = [shoot, shoot_pressed], priority = 2)]
     fn exti2(c: exti2::Context){
-        let mut game = c.resources.game;
+        let mut shoot_pressed = c.resources.shoot_pressed;
         let mut shoot = c.resources.shoot;
 
-        // spawn a bullet
+        // bullet is spawned on the next frame, so that it can be recorded
         shoot.lock(|button:&mut ButtonShoot|{
             // clear the interrput first
             button.clear_interrupt_pending_bit();
-            game.lock(|game:&mut GamePool|{
-                if game.player.can_shoot(){
-                    game.player.shoot();
-                }
+            shoot_pressed.lock(|pressed|{
+                *pressed = true;
             });
         });
     }
 
-    #[task(resources = [game, disp, delay], priority = 5)]
-    fn game_over(c:game_over::Context){
-        let mut game = c.resources.game;
-        let score = game.lock(|game|{
-            game.player.player_score
+    #[task(binds = EXTI3, resources = [shoot_2, shoot_2_pressed], priority = 2)]
+    fn exti3(c: exti3::Context){
+        let mut shoot_2_pressed = c.resources.shoot_2_pressed;
+        let mut shoot = c.resources.shoot_2;
+
+        shoot.lock(|button:&mut ButtonShoot2|{
+            button.clear_interrupt_pending_bit();
+            shoot_2_pressed.lock(|pressed|{
+                *pressed = true;
+            });
         });
-        let mut display = c.resources.disp;
-        let mut delay = c.resources.delay;
-        display.lock(|display|{
-            delay.lock(|delay|{
-                space_war::final_screen(score, display, delay);
-            })
-        })
     }
 
-    // pause has lower priority than game over state,
-    // so that you can't pause in game over state only reset
-    // and has higher priority than others so that no other interrupt can pull
-    // out of pause state except pause.
-    #[task(binds = EXTI9_5, resources = [disp, delay, pause], priority = 4)]
+    // pause is toggled by the game on the next frame, so it never blocks
+    #[task(binds = EXTI9_5, resources = [pause, pause_pressed], priority = 2)]
     fn pause(c: pause::Context){
-        let mut disp = c.resources.disp;
+        let mut pause_pressed = c.resources.pause_pressed;
         let mut intr = c.resources.pause;
 
         intr.lock(| pin:&mut Pause|{
             pin.clear_interrupt_pending_bit();
-        });
-        static mut PAUSED:bool = true;
-        // SAFETY: safe usage of static local variable
-        let paused = unsafe{
-             &mut PAUSED
-        };
-
-
-        if *paused{
-            disp.lock(|display|{
-                space_war::display_pause(display);
+            pause_pressed.lock(|pressed|{
+                *pressed = true;
             });
-            *paused = false;
-        rtic::pend(stm32f7xx_hal::interrupt::EXTI9_5);
-            loop{
-            }
-        } else{
-            defmt::debug!("pause remove");
-            *paused = true;
-        }
-
-        // pause will spawn empty loop so that only pause intr and reset can pull out from it
-    }
-    
-    // after paused this loop will continue, only pause and reset can break this 
-    #[task(priority = 3)]
-    fn empty_loop(_: empty_loop::Context){
-        defmt::debug!("state paused");
-        rtic::pend(stm32f7xx_hal::interrupt::EXTI9_5);
+        });
     }
 
-    #[task(binds = TIM2, resources = [shoot, game, exti, timer2], priority = 2)]
+    // only there to wake up the main loop
+    #[task(binds = TIM2, resources = [timer2], priority = 2)]
     fn tim2(c: tim2::Context){
-        let mut game = c.resources.game;
         let mut timer = c.resources.timer2;
-        game.lock(|game:&mut GamePool|{
-            game.set_fps();
-        });
         // clear interrupt
         timer.lock(|timer:&mut Timer<TIM2>|{
             timer.clear_interrupt(Event::TimeOut);
-            timer.unlisten(Event::TimeOut);
         });
     }
 }
+
+/// prints the replay of the game, so that it can be played again in the simulator
+fn dump_replay(recording:&Recording){
+    let mut buf = [0u8; Recording::MAX_SERIALIZED_LEN];
+    if let Ok(len) = recording.write_to(&mut buf){
+        defmt::info!("replay: {:[u8]}", &buf[..len]);
+    }
+}
@@ -0,0 +1,299 @@
+// Headless simulator, runs the game on the host against an in memory framebuffer.
+//
+// usage: space-war-sim [--script FILE | --replay FILE] [--record FILE] [--frames N] [--seed N]
+//                      [--players N] [--dump N,M,..] [--dump-every N] [--out DIR] [--realtime]
+//                      [--profile]
+//
+// script file has one entry per line `<frames> <keys>`, keys are held for that many frames.
+// keys are any of `l` (left), `r` (right), `s` (shoot), `p` (pause) or `-` for nothing,
+// `#` starts a comment. shoot and pause trigger once when pressed, like the buttons on the board.
+// `L`, `R` and `S` are the buttons of the second player in a `--players 2` game.
+//
+// --replay plays a recording (seed and input) instead of a script, --record saves the played game
+// so that it can be replayed or attached to a bug report.
+//
+// --realtime runs at the tick rate of the board instead of as fast as possible.
+// --profile shows the profiler overlay in dumped frames and prints the numbers at the end.
+//
+//      # wait for a second, then move left while shooting
+//      10 -
+//      20 ls
+
+use std::{
+    env,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    thread,
+    time::{Duration, Instant},
+};
+
+use embedded_graphics::{
+    prelude::*,
+    pixelcolor::BinaryColor,
+};
+
+use space_war::{
+    GamePool,
+    draw_final_screen,
+    framebuffer::{FrameBuffer, PBM_HEADER},
+    input::InputFrame,
+    replay::Recording,
+    rng::XorShift32,
+    profiler::{CycleCounter, Phase, Probe, Profiler},
+    timing::{Clock, FixedStep, Step},
+};
+
+/// keys held during a single frame
+#[derive(Debug, Clone, Copy, Default)]
+struct Keys{
+    left: bool,
+    right: bool,
+    shoot: bool,
+    pause: bool,
+    left_2: bool,
+    right_2: bool,
+    shoot_2: bool,
+}
+
+struct Config{
+    script: Vec<InputFrame>,
+    frames: usize,
+    seed: u32,
+    players: u8,
+    record: Option<PathBuf>,
+    dump: Vec<usize>,
+    dump_every: Option<usize>,
+    out: PathBuf,
+    realtime: bool,
+    profile: bool,
+}
+
+/// wall clock for --realtime and --profile
+struct StdClock(Instant);
+
+impl Clock for StdClock{
+    fn now_us(&mut self)->u32{
+        self.0.elapsed().as_micros() as u32
+    }
+}
+
+/// profiler counts nanoseconds on the host
+impl CycleCounter for StdClock{
+    fn cycles(&mut self)->u32{
+        self.0.elapsed().as_nanos() as u32
+    }
+}
+
+fn main(){
+    let config = match parse_args(){
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("space-war-sim: {}", err);
+            process::exit(2);
+        }
+    };
+    if let Err(err) = run(&config){
+        eprintln!("space-war-sim: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(config:&Config)->io::Result<()>{
+    fs::create_dir_all(&config.out)?;
+    let mut disp = FrameBuffer::new();
+    let mut rng = XorShift32::new(config.seed);
+    let mut game = GamePool::with_players(disp.size(), config.players);
+    let mut recording = Recording::new(config.seed, config.players);
+    let mut clock = StdClock(Instant::now());
+    let mut step = FixedStep::new(clock.now_us());
+    let mut profiler = Profiler::new(StdClock(Instant::now()));
+    if config.profile{
+        profiler.toggle_overlay();
+    }
+
+    for frame in 0..config.frames{
+        if config.realtime{
+            wait_for_tick(&mut step, &mut clock);
+            game.set_fps(step.fps());
+        }
+        // after the script runs out nothing is pressed
+        let input = config.script.get(frame).copied().unwrap_or_default();
+        if recording.record(input).is_err(){
+            eprintln!("space-war-sim: recording is full, frame {} and later are not recorded", frame);
+        }
+        game.tick_probed(input, &mut rng, &mut profiler);
+
+        disp.clear(BinaryColor::Off).unwrap();
+        profiler.measure(Phase::Draw, ||{
+            game.draw(&mut disp).unwrap();
+            game.draw_stats(&mut disp).unwrap();
+        });
+        if profiler.overlay(){
+            profiler.draw_overlay(&mut disp).unwrap();
+        }
+        profiler.end_frame();
+
+        let dump_this = config.dump.contains(&frame)
+            || config.dump_every.map_or(false, |every| frame % every == 0);
+        if dump_this{
+            write_pbm(&config.out.join(format!("frame_{:05}.pbm", frame)), &disp)?;
+        }
+
+        if game.is_ok(){
+            println!("game over at frame {} with score {:?}", frame, game.scores());
+            disp.clear(BinaryColor::Off).unwrap();
+            draw_final_screen(&game.scores(), false, &mut disp).unwrap();
+            write_pbm(&config.out.join("final.pbm"), &disp)?;
+            print_profile(config, &profiler);
+            return save_recording(config, &recording);
+        }
+    }
+    println!("ran {} frames, score {:?}", config.frames, game.scores());
+    print_profile(config, &profiler);
+    save_recording(config, &recording)
+}
+
+/// last complete window of the profiler, in nanoseconds
+fn print_profile(config:&Config, profiler:&Profiler<StdClock>){
+    if !config.profile{
+        return;
+    }
+    for &phase in Phase::ALL.iter(){
+        let summary = profiler.summary(phase);
+        println!("{:>8}: min {:>8}ns avg {:>8}ns max {:>8}ns", phase.name(), summary.min, summary.avg, summary.max);
+    }
+}
+
+/// sleeps till the next tick is due, like the board does with WFI
+fn wait_for_tick(step:&mut FixedStep, clock:&mut StdClock){
+    loop{
+        match step.poll(clock.now_us()){
+            Step::Tick => return,
+            // every tick is drawn anyway
+            Step::Draw => {},
+            Step::Idle => thread::sleep(Duration::from_micros(step.until_next_tick() as u64)),
+        }
+    }
+}
+
+fn save_recording(config:&Config, recording:&Recording)->io::Result<()>{
+    if let Some(path) = &config.record{
+        let mut buf = vec![0; recording.serialized_len()];
+        recording.write_to(&mut buf).expect("buffer is sized for the recording");
+        fs::write(path, buf)?;
+    }
+    Ok(())
+}
+
+/// writes the framebuffer as binary (P4) PBM
+fn write_pbm(path:&Path, disp:&FrameBuffer)->io::Result<()>{
+    let mut file = fs::File::create(path)?;
+    file.write_all(PBM_HEADER.as_bytes())?;
+    file.write_all(disp.as_bytes())
+}
+
+fn parse_args()->Result<Config, String>{
+    let mut config = Config{
+        script: Vec::new(),
+        frames: 1000,
+        seed: 1,
+        players: 1,
+        record: None,
+        dump: Vec::new(),
+        dump_every: None,
+        out: PathBuf::from("frames"),
+        realtime: false,
+        profile: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next(){
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str(){
+            "--script" => {
+                let path = value()?;
+                let text = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+                config.script = parse_script(&text).map_err(|err| format!("{}:{}", path, err))?;
+            },
+            "--replay" => {
+                let path = value()?;
+                let data = fs::read(&path).map_err(|err| format!("{}: {}", path, err))?;
+                let recording = Recording::read_from(&data)
+                    .map_err(|err| format!("{}: invalid recording {:?}", path, err))?;
+                config.seed = recording.seed();
+                config.players = recording.players();
+                config.frames = recording.len() as usize;
+                config.script = recording.frames().collect();
+            },
+            "--record" => config.record = Some(PathBuf::from(value()?)),
+            "--frames" => config.frames = parse_num(&value()?)?,
+            "--seed" => config.seed = parse_num(&value()?)?,
+            "--players" => {
+                config.players = parse_num(&value()?)?;
+                if config.players != 1 && config.players != 2{
+                    return Err(format!("only 1 or 2 players can play, not {}", config.players));
+                }
+            },
+            "--dump" => {
+                for frame in value()?.split(','){
+                    config.dump.push(parse_num(frame)?);
+                }
+            },
+            "--dump-every" => config.dump_every = Some(parse_num::<usize>(&value()?)?.max(1)),
+            "--out" => config.out = PathBuf::from(value()?),
+            "--realtime" => config.realtime = true,
+            "--profile" => config.profile = true,
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(config)
+}
+
+fn parse_num<T: std::str::FromStr>(val:&str)->Result<T, String>{
+    val.trim().parse().map_err(|_| format!("invalid number {:?}", val))
+}
+
+/// expands the script into one entry per frame
+fn parse_script(text:&str)->Result<Vec<InputFrame>, String>{
+    let mut frames = Vec::new();
+    let mut prev = Keys::default();
+    for (line_no, line) in text.lines().enumerate(){
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty(){
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let count:usize = parse_num(parts.next().unwrap_or(""))
+            .map_err(|err| format!("{}: {}", line_no + 1, err))?;
+        let mut keys = Keys::default();
+        for key in parts.next().unwrap_or("-").chars(){
+            match key{
+                'l' => keys.left = true,
+                'r' => keys.right = true,
+                's' => keys.shoot = true,
+                'p' => keys.pause = true,
+                'L' => keys.left_2 = true,
+                'R' => keys.right_2 = true,
+                'S' => keys.shoot_2 = true,
+                '-' => {},
+                _ => return Err(format!("{}: unknown key {:?}", line_no + 1, key)),
+            }
+        }
+        for _ in 0..count{
+            // shoot and pause are only reported on the frame they are pressed
+            frames.push(InputFrame::new(
+                    keys.left,
+                    keys.right,
+                    keys.shoot && !prev.shoot,
+                    keys.pause && !prev.pause,
+                    ).with_player_2(
+                    keys.left_2,
+                    keys.right_2,
+                    keys.shoot_2 && !prev.shoot_2,
+                    ));
+            prev = keys;
+        }
+    }
+    Ok(frames)
+}
@@ -0,0 +1,124 @@
+// board specific glue for stm32f767zi nucleo with ssd1306 display
+use crate::{
+    types::*,
+    input::Input,
+    rng::{GameRng, XorShift32},
+    profiler::CycleCounter,
+    timing::Clock,
+};
+
+use core:: sync::atomic::{AtomicUsize, Ordering};
+
+use defmt_rtt as _; // global logger
+use panic_probe as _;
+
+use cortex_m::peripheral::{DCB, DWT};
+
+use stm32f7xx_hal::{
+    prelude::*,
+    rng::Rng,
+    rcc::Clocks,
+};
+
+impl Input for (Left, Right){
+    fn left(&self)->bool{
+        self.0.is_low().unwrap_or(false)
+    }
+    fn right(&self)->bool{
+        self.1.is_low().unwrap_or(false)
+    }
+}
+
+impl Input for (Left2, Right2){
+    fn left(&self)->bool{
+        self.0.is_low().unwrap_or(false)
+    }
+    fn right(&self)->bool{
+        self.1.is_low().unwrap_or(false)
+    }
+}
+
+/// hardware random number generator, falls back to software generator if the peripheral
+/// reports an error (seed or clock error)
+pub struct HardwareRng{
+    rng: Rng,
+    fallback: XorShift32,
+}
+
+impl HardwareRng{
+    pub fn new(mut rng:Rng)->Self{
+        // seed the fallback from the hardware too, so it is not same on every boot
+        let seed = rng.get_rand().unwrap_or(0);
+        Self{ rng, fallback: XorShift32::new(seed) }
+    }
+}
+
+impl GameRng for HardwareRng{
+    fn next_u32(&mut self)->u32{
+        match self.rng.get_rand(){
+            Ok(val) => val,
+            Err(_) => {
+                defmt::warn!("couldn't generate random value from hardware. using software rng");
+                self.fallback.next_u32()
+            }
+        }
+    }
+}
+
+/// microsecond clock from the cycle counter. the counter wraps every few minutes at 32MHz, so
+/// `now_us` has to be called more often than that, which the main loop does every tick
+pub struct DwtClock{
+    cycles_per_us: u32,
+    last: u32,
+    // cycles not yet counted as a whole microsecond
+    rem: u32,
+    us: u32,
+}
+
+impl DwtClock{
+    pub fn new(dcb:&mut DCB, dwt:&mut DWT, clocks:&Clocks)->Self{
+        dcb.enable_trace();
+        dwt.enable_cycle_counter();
+        Self{
+            cycles_per_us: (clocks.sysclk().0 / 1_000_000).max(1),
+            last: DWT::get_cycle_count(),
+            rem: 0,
+            us: 0,
+        }
+    }
+}
+
+impl Clock for DwtClock{
+    fn now_us(&mut self)->u32{
+        let cycles = DWT::get_cycle_count();
+        let elapsed = cycles.wrapping_sub(self.last).wrapping_add(self.rem);
+        self.last = cycles;
+        self.us = self.us.wrapping_add(elapsed / self.cycles_per_us);
+        self.rem = elapsed % self.cycles_per_us;
+        self.us
+    }
+}
+
+/// raw cycle counter for the profiler, enabled by `DwtClock::new`
+pub struct DwtCycles;
+
+impl CycleCounter for DwtCycles{
+    fn cycles(&mut self)->u32{
+        DWT::get_cycle_count()
+    }
+}
+
+#[defmt::timestamp]
+fn timestamp() -> u64 {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.load(Ordering::Relaxed);
+    COUNT.store(n + 1, Ordering::Relaxed);
+    n as u64
+}
+
+/// Terminates the application and makes `probe-run` exit with exit-code = 0
+pub fn exit() -> ! {
+    loop {
+        cortex_m::asm::bkpt();
+    }
+}
@@ -0,0 +1,126 @@
+// Fixed point numbers for positions and velocities, so that objects can move by fractions of a
+// pixel without floats.
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// 12.4 fixed point: 12 bits of whole pixels (±2048) and 4 bits (1/16) of fraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fx(i16);
+
+impl Fx{
+    pub const FRAC_BITS: u32 = 4;
+    pub const ZERO: Fx = Fx(0);
+    pub const ONE: Fx = Fx(1 << Self::FRAC_BITS);
+
+    pub const fn from_int(val:i16)->Self{
+        Fx(val << Self::FRAC_BITS)
+    }
+    /// from sixteenths of a pixel
+    pub const fn from_raw(raw:i16)->Self{
+        Fx(raw)
+    }
+    pub const fn raw(self)->i16{
+        self.0
+    }
+    /// whole pixels, rounded down
+    pub const fn to_int(self)->i16{
+        self.0 >> Self::FRAC_BITS
+    }
+    pub fn abs(self)->Self{
+        Fx(self.0.abs())
+    }
+    /// moves towards zero by `amount`, without crossing it
+    pub fn towards_zero(self, amount:Fx)->Self{
+        if self.0 > amount.0{
+            Fx(self.0 - amount.0)
+        } else if self.0 < -amount.0{
+            Fx(self.0 + amount.0)
+        } else {
+            Fx::ZERO
+        }
+    }
+    /// moves towards `target` by at most `step`
+    pub fn approach(self, target:Fx, step:Fx)->Self{
+        self + (target - self).clamp_abs(step)
+    }
+    /// limits to `-max..=max`
+    pub fn clamp_abs(self, max:Fx)->Self{
+        Fx(self.0.max(-max.0).min(max.0))
+    }
+}
+
+impl Add for Fx{
+    type Output = Fx;
+    fn add(self, other:Fx)->Fx{
+        Fx(self.0 + other.0)
+    }
+}
+impl Sub for Fx{
+    type Output = Fx;
+    fn sub(self, other:Fx)->Fx{
+        Fx(self.0 - other.0)
+    }
+}
+impl AddAssign for Fx{
+    fn add_assign(&mut self, other:Fx){
+        self.0 += other.0;
+    }
+}
+impl SubAssign for Fx{
+    fn sub_assign(&mut self, other:Fx){
+        self.0 -= other.0;
+    }
+}
+impl Neg for Fx{
+    type Output = Fx;
+    fn neg(self)->Fx{
+        Fx(-self.0)
+    }
+}
+/// scales by a whole number
+impl Mul<i16> for Fx{
+    type Output = Fx;
+    fn mul(self, val:i16)->Fx{
+        Fx(self.0 * val)
+    }
+}
+impl Mul for Fx{
+    type Output = Fx;
+    fn mul(self, other:Fx)->Fx{
+        Fx(((self.0 as i32 * other.0 as i32) >> Self::FRAC_BITS) as i16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_pixels_round_down(){
+        assert_eq!(Fx::from_int(3).to_int(), 3);
+        assert_eq!(Fx::from_raw(8).to_int(), 0);
+        assert_eq!(Fx::from_raw(-8).to_int(), -1);
+        assert_eq!((Fx::from_int(2) - Fx::from_raw(1)).to_int(), 1);
+    }
+
+    #[test]
+    fn fractions_add_up(){
+        let mut pos = Fx::from_int(10);
+        // half a pixel a frame
+        for _ in 0..4{
+            pos += Fx::from_raw(8);
+        }
+        assert_eq!(pos, Fx::from_int(12));
+        assert_eq!(Fx::from_raw(8) * Fx::from_raw(8), Fx::from_raw(4));
+        assert_eq!(Fx::from_raw(8) * 3, Fx::from_raw(24));
+    }
+
+    #[test]
+    fn friction_stops_at_zero(){
+        assert_eq!(Fx::from_raw(5).towards_zero(Fx::from_raw(4)), Fx::from_raw(1));
+        assert_eq!(Fx::from_raw(3).towards_zero(Fx::from_raw(4)), Fx::ZERO);
+        assert_eq!(Fx::from_raw(-3).towards_zero(Fx::from_raw(4)), Fx::ZERO);
+        assert_eq!(Fx::from_raw(-40).clamp_abs(Fx::from_int(2)), Fx::from_int(-2));
+        assert_eq!(Fx::from_int(5).approach(Fx::from_int(3), Fx::ONE), Fx::from_int(4));
+        assert_eq!(Fx::from_raw(3).approach(Fx::ZERO, Fx::ONE), Fx::ZERO);
+    }
+}
@@ -0,0 +1,75 @@
+use embedded_graphics::{
+    prelude::*,
+    pixelcolor::BinaryColor,
+    DrawTarget,
+};
+
+/// width of the rotated ssd1306 panel
+pub const WIDTH:usize = 64;
+/// height of the rotated ssd1306 panel
+pub const HEIGHT:usize = 128;
+/// header of a binary PBM file holding a frame, raster is `FrameBuffer::as_bytes`
+pub const PBM_HEADER:&str = "P4\n64 128\n";
+
+/// In memory 1 bit display with the same geometry as the rotated ssd1306 panel.
+///
+/// pixels are stored row major and MSB first, the same layout `ImageRaw<BinaryColor>` uses,
+/// so `as_bytes` can be written out as the raster of a binary PBM file as is.
+#[derive(Clone, PartialEq)]
+pub struct FrameBuffer{
+    buffer: [u8; WIDTH * HEIGHT / 8],
+}
+
+impl FrameBuffer{
+    pub fn new()->Self{
+        Self{ buffer: [0; WIDTH * HEIGHT / 8] }
+    }
+    /// returns true if the pixel is on, pixels outside of the panel are off
+    pub fn get_pixel(&self, x:usize, y:usize)->bool{
+        if x >= WIDTH || y >= HEIGHT{
+            return false;
+        }
+        self.buffer[y * WIDTH / 8 + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+    pub fn as_bytes(&self)->&[u8]{
+        &self.buffer
+    }
+    /// loads a frame saved with `as_bytes`, returns None if the size doesn't match
+    pub fn from_bytes(bytes:&[u8])->Option<Self>{
+        let mut frame = Self::new();
+        if bytes.len() != frame.buffer.len(){
+            return None;
+        }
+        frame.buffer.copy_from_slice(bytes);
+        Some(frame)
+    }
+}
+
+impl Default for FrameBuffer{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
+impl DrawTarget<BinaryColor> for FrameBuffer{
+    type Error = core::convert::Infallible;
+
+    fn draw_pixel(&mut self, Pixel(point, color): Pixel<BinaryColor>)->Result<(), Self::Error>{
+        // just like the display, anything drawn outside of the panel is dropped
+        if point.x < 0 || point.y < 0 || point.x as usize >= WIDTH || point.y as usize >= HEIGHT{
+            return Ok(());
+        }
+        let (x, y) = (point.x as usize, point.y as usize);
+        let index = y * WIDTH / 8 + x / 8;
+        let mask = 0x80 >> (x % 8);
+        match color{
+            BinaryColor::On => self.buffer[index] |= mask,
+            BinaryColor::Off => self.buffer[index] &= !mask,
+        }
+        Ok(())
+    }
+
+    fn size(&self)->Size{
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
@@ -1,6 +1,8 @@
 // import Section 
 use crate::{
-    types::{Left, Right, Display},
+    animation::{Animation, Mode, SpriteSheet},
+    fixed::Fx,
+    input::Input,
     objects::*,
 };
 use embedded_graphics::{
@@ -10,7 +12,7 @@ use embedded_graphics::{
     drawable::Drawable,
     style::Styled,
     style::PrimitiveStyle,
-    primitives::Rectangle,
+    primitives::{Circle, Rectangle},
 };
 
 use heapless::{
@@ -18,51 +20,270 @@ use heapless::{
     consts::*,
 };
 
-use stm32f7xx_hal::prelude::*;
-
 // Structs definitions
 #[derive(Debug)]
 pub struct Player {
-    x:i16,
-    y:i16,
-    vel_x:i16,
-    vel_y:i16,
+    x:Fx,
+    y:Fx,
+    vel_x:Fx,
+    vel_y:Fx,
     pub active: bool,
-    pub bullets:Vec<Bullet, U3>,
+    pub bullets:Vec<Bullet, U18>,
     raw_image: ImageRaw<'static, BinaryColor>,
     pub player_score:i16,
+    pub lives:u8,
+    // hits left before the ship is lost
+    health:u8,
+    // frames left of the explosion after being hit
+    dying:u8,
+    // frames left in which player can't be hit
+    invulnerable:u16,
+    engine: Animation,
+    explosion: Animation,
+    weapon:Weapon,
+    // volleys left in the magazine
+    rounds:u8,
+    // ticks left till the magazine is full again, 0 when not reloading
+    reload:u16,
+    // ticks since last round came back
+    refill:u16,
+    heat:u8,
+    overheated:bool,
+    // ticks left of timed power-ups
+    rapid_fire:u16,
+    triple_shot:u16,
+    shield:u16,
 }
 
 #[derive(Debug)]
 pub struct Enemy {
-    x:i16,
-    y:i16,
+    x:Fx,
+    y:Fx,
+    vel_x:Fx,
+    vel_y:Fx,
+    // where the enemy flies to and moves around, its slot when flying in formation
+    home_x:Fx,
+    home_y:Fx,
+    behaviour:Behaviour,
+    flight:Flight,
+    // ticks since the enemy got home, drives the movement
+    ticks:u16,
+    fire:FirePattern,
+    // bullets in a fan or a burst
+    shots:u8,
+    // patterns fired so far
+    attack:u8,
     // number bullets are created by enemy and number of active
-    raw_image: ImageRaw<'static, BinaryColor>,
+    animation: Animation,
     pub active:bool,
+    health:u8,
+    max_health:u8,
+    // ticks left of blinking after a hit
+    flash:u8,
+    // index of the player who shot it down
+    pub killer:u8,
     pub bullet_cool_down: u16,
     cool_down:u16,
 }
 
+/// How an enemy moves once it is in place, picked per enemy when it spawns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour{
+    /// stays where it was put
+    Hover,
+    /// flies from side to side, turning at the borders
+    Strafe,
+    /// sways from side to side around where it spawned
+    SineWave,
+    /// waits a while, dives at the nearest player and comes back in from the top
+    DiveBomb,
+    /// flies in from above the screen to its slot and sways there with the rest of the formation
+    Formation,
+}
+
+/// How an enemy shoots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirePattern{
+    /// one bullet straight down
+    Straight,
+    /// one bullet at the nearest player
+    Aimed,
+    /// fan of bullets spreading out downwards
+    Spread,
+    /// a few bullets straight down in quick succession
+    Burst,
+    /// aimed bullet and fan taking turns
+    Alternating,
+}
+
+/// Type of an enemy, how it moves and how it shoots. see HOVERER and the others in objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnemyKind{
+    pub behaviour:Behaviour,
+    pub fire:FirePattern,
+    /// hits it takes, armoured enemies take several and score more
+    pub health:u8,
+}
+
+// what an enemy is doing right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flight{
+    // on the way home
+    Entering,
+    // moving around home as its behaviour says
+    Holding,
+    Diving,
+}
+
+#[derive(Debug)]
+pub struct Boss {
+    x:Fx,
+    y:Fx,
+    vel_x:Fx,
+    raw_image: ImageRaw<'static, BinaryColor>,
+    pub active:bool,
+    health:u8,
+    max_health:u8,
+    // ticks left of blinking after a hit
+    flash:u8,
+    // frames since spawn, drives the movement
+    ticks:u16,
+    // which attack pattern is fired next
+    attack:u8,
+    // index of the player who landed the last hit
+    pub killer:u8,
+    pub bullet_cool_down: u16,
+}
+
 #[derive(Debug)]
 pub struct Bullet {
-    x:i16,
-    y:i16,
+    x:Fx,
+    y:Fx,
     friendly:bool,
-    vel_x:i8,       // no need of x vel, they will always move in straight line
-    vel_y:i8,       // no need of x vel, they will always move in straight line
+    vel_x:Fx,
+    vel_y:Fx,
     raw_image: ImageRaw<'static, BinaryColor>,
     pub active: bool,
+    damage:u8,
+    // targets left to go through
+    pierce:u8,
+    // still inside a target it went through, doesn't hit anything till it is out
+    inside:bool,
+    homing:bool,
+}
+
+/// What the player shoots with, see `WeaponSpec` for what each one does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weapon{
+    Single,
+    Spread,
+    Laser,
+    Piercing,
+    Homing,
+}
+
+/// How bullets of a weapon look and behave.
+#[derive(Debug)]
+pub struct WeaponSpec{
+    pub sprite:&'static Sprite,
+    /// shown in the stats bar and on its power-up
+    pub icon:&'static Sprite,
+    pub speed:Fx,
+    /// sideways speed between neighbouring bullets of a volley
+    pub spread:Fx,
+    /// bullets fired at once, odd so that one goes straight up
+    pub shots:u8,
+    /// health a bullet takes off an enemy, asteroid or the boss it hits
+    pub damage:u8,
+    /// targets a bullet goes through before it stops
+    pub pierce:u8,
+    /// bullets turn towards the nearest enemy ahead
+    pub homing:bool,
+    /// volleys in the air at once
+    pub in_air:u8,
+    /// volleys before it has to be reloaded
+    pub magazine:u8,
+    /// ticks a reload takes once the magazine is empty
+    pub reload_ticks:u16,
+    /// heat every volley adds to the gauge, 0 for weapons that don't overheat
+    pub heat:u8,
 }
 
 #[derive(Debug)]
 pub struct Asteroid {
-    x:i16,
-    y:i16,
-    vel_x:i8,
-    vel_y:i8,
-    images: [ImageRaw<'static, BinaryColor>;4],
+    x:Fx,
+    y:Fx,
+    vel_x:Fx,
+    vel_y:Fx,
+    size:AsteroidSize,
+    animation: Animation,
     pub active:bool,
+    health:u8,
+    // ticks left of blinking after a hit
+    flash:u8,
+    // index of the player who shot it down
+    pub killer:u8,
+}
+
+/// Size class of an asteroid, see `AsteroidSpec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsteroidSize{
+    Large,
+    Medium,
+    Small,
+}
+
+/// How an asteroid of a size class looks and what it is worth.
+#[derive(Debug)]
+pub struct AsteroidSpec{
+    pub sheet:&'static SpriteSheet,
+    pub health:u8,
+    pub score:i16,
+    /// what it breaks up into when shot down, two of them
+    pub fragment:Option<AsteroidSize>,
+}
+
+/// Collectible dropped by kills, drifts down till a player flies into it.
+#[derive(Debug)]
+pub struct PowerUp {
+    x:Fx,
+    y:Fx,
+    pub kind:PowerUpKind,
+    pub active:bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind{
+    /// twice as many bullets in the air, for POWER_UP_TICKS
+    RapidFire,
+    /// every shot is three bullets spreading out, for POWER_UP_TICKS
+    TripleShot,
+    /// hits bounce off, for SHIELD_TICKS
+    Shield,
+    ExtraLife,
+    /// destroys everything on screen right away, boss takes BOMB_DAMAGE
+    Bomb,
+    /// switches to the weapon till the ship is lost
+    Weapon(Weapon),
+}
+
+/// short lived eye candy, doesn't take part in collisions
+#[derive(Debug)]
+pub struct Effect {
+    x:Fx,
+    y:Fx,
+    vel_x:Fx,
+    vel_y:Fx,
+    kind: EffectKind,
+    pub active:bool,
+}
+
+#[derive(Debug)]
+pub enum EffectKind{
+    /// explosion played once at the kill location
+    Explosion(Animation),
+    /// single pixel flying away from the kill, for given number of frames
+    Debris(u8),
 }
 
 #[derive(Debug)]
@@ -83,6 +304,7 @@ pub struct Screen{
 pub struct Stats{
     pub border: Styled<Rectangle, PrimitiveStyle<BinaryColor>>,
     pub score:ImageRaw<'static, BinaryColor>,
+    pub lives:ImageRaw<'static, BinaryColor>,
     pub ammo:ImageRaw<'static, BinaryColor>,
 }
 
@@ -113,7 +335,7 @@ pub trait HasBoundary{
 }
 
 pub trait CanDraw{
-    fn draw(&self, disp:&mut Display);
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error>;
 }
 // implementation Section
 impl Screen{
@@ -132,57 +354,190 @@ impl Player{
     pub fn new(x:i16, y:i16, sprite: &Sprite)->Self{
         let raw_image = ImageRaw::new(sprite.data, sprite.width as u32, sprite.height as u32);
         let bullets = Vec::new();
-        Self{ x, y, vel_x:0, vel_y:0, raw_image,
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), vel_x:Fx::ZERO, vel_y:Fx::ZERO, raw_image,
             active:true, bullets,
             player_score:0,
+            lives: PLAYER_LIVES,
+            health: PLAYER_HEALTH,
+            dying: 0,
+            invulnerable: 0,
+            engine: Animation::new(&ENGINE_SHEET, 1, Mode::Loop),
+            explosion: Animation::new(&EXPLOSION_SHEET, EXPLOSION_FRAME_TICKS, Mode::Once),
+            weapon: Weapon::Single,
+            rounds: SINGLE_SHOT.magazine,
+            reload: 0,
+            refill: 0,
+            heat: 0,
+            overheated: false,
+            rapid_fire: 0,
+            triple_shot: 0,
+            shield: 0,
         }
     }
-    pub fn update(&mut self, dir:&(Left, Right), screen:&Screen) {
+    pub fn update<I: Input>(&mut self, dir:&I, screen:&Screen) {
+        if self.invulnerable > 0{
+            self.invulnerable -= 1;
+        }
+        self.rapid_fire = self.rapid_fire.saturating_sub(1);
+        self.triple_shot = self.triple_shot.saturating_sub(1);
+        self.shield = self.shield.saturating_sub(1);
+        // wreck doesn't move
+        if !self.active{
+            if self.dying > 0{
+                self.dying -= 1;
+                self.explosion.tick();
+            }
+            return;
+        }
+        self.engine.tick();
         self.mov(dir);
         self.boundary_check(screen);
         self.x += self.vel_x;
         self.y += self.vel_y;
     }
-    pub fn mov(&mut self, dir:&(Left, Right)){
-        // check if left button is pressed,
-        if let Ok(left) = dir.0.is_low(){
-            if left{
-                self.vel_x = -2;
-                // when left is pressed but right is not
-                // then velocity will be 0. to bypass that we return early
-                return;
-            } else {
-                self.vel_x = 0;
-            }
-        }
-        if let Ok(right) = dir.1.is_low(){
-            if right{
-                self.vel_x = 2;
-            } else {
-                self.vel_x = 0;
-            }
+    pub fn mov<I: Input>(&mut self, dir:&I){
+        // speed up while a button is held down, left wins when both are, slow down otherwise
+        if dir.left(){
+            self.vel_x -= PLAYER_ACCEL;
+        } else if dir.right(){
+            self.vel_x += PLAYER_ACCEL;
+        } else {
+            self.vel_x = self.vel_x.towards_zero(PLAYER_FRICTION);
         }
+        self.vel_x = self.vel_x.clamp_abs(PLAYER_MAX_SPEED);
     }
     pub fn get_corner_pos(&self)->(i16, i16){
         let (x, y) = self.get_pos();
         (x + self.raw_image.width() as i16, y + self.raw_image.height()as i16)
     }
     pub fn shoot(&mut self){
-        let raw_image = ImageRaw::new(BULLET_SPRITE.data, BULLET_SPRITE.width as u32, BULLET_SPRITE.height as u32);
-        let x = self.x + self.raw_image.width() as i16/2 - BULLET_SPRITE.width as i16/2;
+        let spec = self.weapon.spec();
+        self.rounds = self.rounds.saturating_sub(1);
+        if self.rounds == 0{
+            debug!("reloading");
+            self.reload = spec.reload_ticks.max(1);
+        }
+        if spec.heat > 0{
+            self.heat = self.heat.saturating_add(spec.heat).min(HEAT_MAX);
+            self.overheated = self.heat == HEAT_MAX;
+        }
+        let raw_image = ImageRaw::new(spec.sprite.data, spec.sprite.width as u32, spec.sprite.height as u32);
+        let (x, y) = self.get_pos();
+        let x = x + self.raw_image.width() as i16/2 - spec.sprite.width as i16/2;
         // if object is friendly then y = y - bullet height else y = y+bullet height;
-        let y =  self.y - BULLET_SPRITE.height as i16; 
-       match self.bullets.push(Bullet{
-            x,y,
-            friendly: true,
-            vel_y: -3, // if friendly then vel_y is -ve
-            vel_x:0,
-            raw_image,
-            active:true,
-        }){
-           Ok(_) => defmt::debug!("spawning friendly bullet at ({:?}, {:?})", x, y),
-           Err(_)=> defmt::debug!("cant create bullet capacity full"),
-       };
+        let y =  y - spec.sprite.height as i16; 
+        // volley fans out evenly to both sides, triple shot spreads weapons firing straight
+        let shots = self.shot_size() as i16;
+        let spread = if spec.spread == Fx::ZERO { TRIPLE_SHOT_SPREAD } else { spec.spread };
+        for side in -(shots/2)..=shots/2{
+           match self.bullets.push(Bullet{
+                x:Fx::from_int(x),
+                y:Fx::from_int(y),
+                friendly: true,
+                vel_y: spec.speed, // if friendly then vel_y is -ve
+                vel_x:spread * side,
+                raw_image,
+                active:true,
+                damage: spec.damage,
+                pierce: spec.pierce,
+                inside: false,
+                homing: spec.homing,
+            }){
+               Ok(_) => debug!("spawning friendly bullet at ({:?}, {:?})", x, y),
+               Err(_)=> debug!("cant create bullet capacity full"),
+           };
+        }
+    }
+    /// bullets fired at once
+    fn shot_size(&self)->usize{
+        let shots = self.weapon.spec().shots as usize;
+        if self.triple_shot > 0 { shots.max(3) } else { shots }
+    }
+    /// most bullets in the air at once
+    fn max_bullets(&self)->usize{
+        let bullets = self.weapon.spec().in_air as usize * self.shot_size();
+        if self.rapid_fire > 0 { bullets * 2 } else { bullets }
+    }
+    pub fn weapon(&self)->Weapon{
+        self.weapon
+    }
+    /// volleys left in the magazine
+    pub fn ammo(&self)->u8{
+        self.rounds
+    }
+    /// ticks till reloaded and ticks a whole reload takes, None when not reloading
+    pub fn reloading(&self)->Option<(u16, u16)>{
+        if self.reload > 0 { Some((self.reload, self.weapon.spec().reload_ticks.max(1))) } else { None }
+    }
+    /// heat gauge, up to HEAT_MAX
+    pub fn heat(&self)->u8{
+        self.heat
+    }
+    pub fn is_overheated(&self)->bool{
+        self.overheated
+    }
+    /// to be called every tick: reloads an empty magazine, gets a round back every `refill`
+    /// ticks (never when 0) and cools the weapon down
+    pub fn update_ammo(&mut self, refill:u16){
+        let magazine = self.weapon.spec().magazine;
+        self.heat = self.heat.saturating_sub(HEAT_COOLING);
+        if self.heat == 0{
+            self.overheated = false;
+        }
+        if self.reload > 0{
+            self.reload -= 1;
+            if self.reload == 0{
+                self.rounds = magazine;
+                self.refill = 0;
+            }
+            return;
+        }
+        if refill == 0 || self.rounds >= magazine{
+            self.refill = 0;
+            return;
+        }
+        self.refill += 1;
+        if self.refill >= refill{
+            self.refill = 0;
+            self.rounds += 1;
+        }
+    }
+    // full magazine and cold barrel, after a new weapon or ship
+    fn rearm(&mut self){
+        self.rounds = self.weapon.spec().magazine;
+        self.reload = 0;
+        self.refill = 0;
+        self.heat = 0;
+        self.overheated = false;
+    }
+    /// applies a collected power-up, bombs are up to the game
+    pub fn power_up(&mut self, kind:PowerUpKind){
+        match kind{
+            PowerUpKind::RapidFire => self.rapid_fire = POWER_UP_TICKS,
+            PowerUpKind::TripleShot => self.triple_shot = POWER_UP_TICKS,
+            PowerUpKind::Shield => self.shield = SHIELD_TICKS,
+            PowerUpKind::ExtraLife => self.lives = self.lives.saturating_add(1).min(MAX_LIVES),
+            PowerUpKind::Bomb => {},
+            PowerUpKind::Weapon(weapon) => {
+                self.weapon = weapon;
+                self.rearm();
+            },
+        }
+    }
+    /// timed power-up running, with ticks left. shield first, then the other ones
+    pub fn active_power_up(&self)->Option<(PowerUpKind, u16)>{
+        [
+            (PowerUpKind::Shield, self.shield),
+            (PowerUpKind::TripleShot, self.triple_shot),
+            (PowerUpKind::RapidFire, self.rapid_fire),
+        ].iter().copied().find(|&(_, ticks)| ticks > 0)
+    }
+    /// hits left before the ship is lost
+    pub fn health(&self)->u8{
+        self.health
+    }
+    pub fn is_shielded(&self)->bool{
+        self.shield > 0
     }
     fn boundary_check(&mut self, screen:&Screen) {
         // check on both right upper corner and left lower corner if anyone of them crosses the
@@ -191,62 +546,434 @@ impl Player{
         // since player moves only in x axis, checking on x axis is sufficient
         //
         // we are taking new position, if not player will get stuck on hitting border
-        let new_pos = self.x + self.vel_x;
+        let new_pos = (self.x + self.vel_x).to_int();
         if new_pos <= 1 - self.raw_image.width()as i16/2  || new_pos + self.raw_image.width()as i16/2   >= screen.width as i16 {
-            self.vel_x = 0;
+            self.vel_x = Fx::ZERO;
         }
     }
     pub fn can_shoot(&self)->bool{
-        self.bullets.capacity() > self.bullets.len()
+        self.active && self.rounds > 0 && self.reload == 0 && !self.overheated
+            && self.bullets.len() + self.shot_size() <= self.max_bullets()
+    }
+    /// false while dead or invulnerable after respawn
+    pub fn can_be_hit(&self)->bool{
+        self.active && self.invulnerable == 0
+    }
+    /// takes damage, unless shield takes the hit. ship blinks for a moment after a hit it
+    /// survives, once there is no health left it takes a life and starts the explosion
+    pub fn hit(&mut self, damage:u8){
+        if self.shield > 0{
+            return;
+        }
+        self.health = self.health.saturating_sub(damage);
+        if self.health > 0{
+            debug!("player hit, health left: {:?}", self.health);
+            self.invulnerable = HIT_INVULNERABLE_TICKS;
+            return;
+        }
+        debug!("player hit, lives left: {:?}", self.lives.saturating_sub(1));
+        // power-ups are lost with the ship
+        self.weapon = Weapon::Single;
+        self.rapid_fire = 0;
+        self.triple_shot = 0;
+        self.active = false;
+        self.lives = self.lives.saturating_sub(1);
+        self.dying = DEATH_FRAMES;
+        self.explosion.restart();
+        self.vel_x = Fx::ZERO;
+    }
+    /// true while explosion is still going on
+    pub fn is_dying(&self)->bool{
+        self.dying > 0
+    }
+    pub fn is_invulnerable(&self)->bool{
+        self.invulnerable > 0
+    }
+    /// brings player back at given position, blinking and invulnerable for a while
+    pub fn respawn(&mut self, x:i16, y:i16){
+        self.set_pos(x, y);
+        self.vel_x = Fx::ZERO;
+        self.active = true;
+        self.dying = 0;
+        self.invulnerable = INVULNERABLE_FRAMES;
+        self.health = PLAYER_HEALTH;
+        self.rearm();
+    }
+}
+
+impl EnemyKind{
+    /// number of enemies spawned for it
+    pub fn size(&self)->usize{
+        if self.behaviour == Behaviour::Formation { FORMATION_SIZE } else { 1 }
     }
 }
 
 impl Enemy{
-    pub fn new(x:i16, y:i16, sprite: &Sprite, cool_down:u16)->Self{
-        let raw_image = ImageRaw::new(sprite.data, sprite.width as u32, sprite.height as u32);
-        defmt::debug!("spawn: Enemy at ({:?}, {:?})", x,y);
-        Self{ x, y, raw_image, active:true, bullet_cool_down:40, cool_down:cool_down*2+50}
+    /// hovering enemy, shooting straight down every cool_down ticks
+    pub fn new(x:i16, y:i16, sheet: &'static SpriteSheet, cool_down:u16)->Self{
+        // enemies spawned together shouldn't blink together
+        let animation = Animation::new(sheet, ENEMY_FRAME_TICKS, Mode::Loop).starting_at(x as usize);
+        debug!("spawn: Enemy at ({:?}, {:?})", x,y);
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), vel_x:Fx::ZERO, vel_y:Fx::ZERO,
+            home_x:Fx::from_int(x), home_y:Fx::from_int(y),
+            behaviour:Behaviour::Hover, flight:Flight::Entering, ticks:0,
+            fire:FirePattern::Straight, shots:1, attack:0,
+            animation, active:true, health:1, max_health:1, flash:0, killer:0, bullet_cool_down:40, cool_down}
+    }
+    /// takes given number of hits
+    pub fn with_health(mut self, health:u8)->Self{
+        self.health = health.max(1);
+        self.max_health = self.health;
+        self
+    }
+    /// moves as behaviour says instead of hovering
+    pub fn with_behaviour(mut self, behaviour:Behaviour)->Self{
+        self.behaviour = behaviour;
+        if behaviour == Behaviour::Strafe{
+            self.vel_x = ENEMY_STRAFE_SPEED;
+        }
+        self
     }
-    pub fn update(&mut self) {
-        // no need of boundary check for enemy
+    /// shoots with given pattern instead of straight down, fans and bursts get denser with level
+    pub fn with_fire(mut self, fire:FirePattern, level:u8)->Self{
+        let extra = level / ENEMY_SHOT_LEVELS;
+        self.fire = fire;
+        self.shots = match fire{
+            FirePattern::Spread | FirePattern::Alternating => ENEMY_SPREAD_SHOTS.saturating_add(extra),
+            FirePattern::Burst => ENEMY_BURST_SHOTS.saturating_add(extra),
+            _ => 1,
+        }.min(ENEMY_MAX_SHOTS);
+        self
+    }
+    /// moves, shoots and takes hits as the kind says, see `with_behaviour`, `with_fire` and
+    /// `with_health`
+    pub fn of_kind(self, kind:EnemyKind, level:u8)->Self{
+        self.with_behaviour(kind.behaviour).with_fire(kind.fire, level).with_health(kind.health)
+    }
+    pub fn health(&self)->u8{
+        self.health
+    }
+    /// takes damage, shot down once there is no health left
+    pub fn damage(&mut self, damage:u8){
+        self.health = self.health.saturating_sub(damage);
+        if self.health == 0{
+            self.active = false;
+        } else {
+            self.flash = HIT_FLASH_TICKS;
+        }
+    }
+    /// starts at given position, usually above the screen, and flies to where it was spawned
+    /// before doing anything else
+    pub fn entering_from(mut self, x:i16, y:i16)->Self{
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
+        self
+    }
+    pub fn behaviour(&self)->Behaviour{
+        self.behaviour
+    }
+    pub fn fire(&self)->FirePattern{
+        self.fire
+    }
+    pub fn is_diving(&self)->bool{
+        self.flight == Flight::Diving
+    }
+    /// targets are the middle of every player that can be dived at
+    pub fn update(&mut self, screen:&Screen, targets:&[(i16, i16)]) {
         // count down counter
         if self.bullet_cool_down > 0{
             self.bullet_cool_down -=1;
         }
+        self.animation.tick();
+        self.flash = self.flash.saturating_sub(1);
+        self.ticks = self.ticks.wrapping_add(1);
+        match self.flight{
+            Flight::Entering => {
+                self.x = self.x.approach(self.home_x, ENEMY_ENTRY_SPEED);
+                self.y = self.y.approach(self.home_y, ENEMY_ENTRY_SPEED);
+                if self.x == self.home_x && self.y == self.home_y{
+                    self.flight = Flight::Holding;
+                    self.ticks = 0;
+                }
+            },
+            Flight::Holding => self.hold(screen, targets),
+            Flight::Diving => self.dive(screen, targets),
+        }
+    }
+    fn hold(&mut self, screen:&Screen, targets:&[(i16, i16)]){
+        match self.behaviour{
+            Behaviour::Hover => return,
+            Behaviour::Strafe => self.x += self.vel_x,
+            Behaviour::SineWave => self.x = self.home_x + Fx::from_int(Self::sway(self.ticks)),
+            // formation sways less, so that its members don't run into the borders
+            Behaviour::Formation => self.x = self.home_x + Fx::from_int(Self::sway(self.ticks)/2),
+            Behaviour::DiveBomb => {
+                if self.ticks >= ENEMY_DIVE_WAIT && !targets.is_empty(){
+                    debug!("enemy diving");
+                    self.flight = Flight::Diving;
+                    self.vel_x = Fx::ZERO;
+                    self.vel_y = ENEMY_DIVE_SPEED;
+                }
+            },
+        }
+        self.boundary_check(screen);
+    }
+    fn dive(&mut self, screen:&Screen, targets:&[(i16, i16)]){
+        // steer towards the nearest player
+        if let Some((target, _)) = self.nearest(targets){
+            let (center, _) = self.center();
+            self.vel_x = (self.vel_x + ENEMY_DIVE_STEER * (target - center).signum()).clamp_abs(ENEMY_DIVE_SPEED);
+        }
+        self.x += self.vel_x;
+        self.y += self.vel_y;
+        self.boundary_check(screen);
+        // past the bottom, come back in from the top
+        if self.y.to_int() > screen.height() as i16{
+            self.x = self.home_x;
+            self.y = Fx::from_int(-(self.animation.height() as i16));
+            self.vel_x = Fx::ZERO;
+            self.vel_y = Fx::ZERO;
+            self.flight = Flight::Entering;
+        }
+    }
+    fn center(&self)->(i16, i16){
+        let (x, y) = self.get_pos();
+        (x + self.animation.width() as i16/2, y + self.animation.height() as i16/2)
+    }
+    fn nearest(&self, targets:&[(i16, i16)])->Option<(i16, i16)>{
+        let (x, y) = self.center();
+        targets.iter().copied().min_by_key(|&(target_x, target_y)| (target_x - x).abs() + (target_y - y).abs())
+    }
+    // sideways offset of swaying enemies, in pixels
+    fn sway(ticks:u16)->i16{
+        ENEMY_SWAY[(ticks / ENEMY_SWAY_TICKS) as usize % ENEMY_SWAY.len()] as i16
+    }
+    fn boundary_check(&mut self, screen:&Screen){
+        let min_x = Fx::ONE;
+        let max_x = Fx::from_int(screen.width() as i16 - self.animation.width() as i16);
+        if self.x < min_x || self.x > max_x{
+            self.x = self.x.max(min_x).min(max_x);
+            self.vel_x = -self.vel_x;
+        }
     }
     pub fn get_corner_pos(&self)->(i16, i16){
         let (x, y) = self.get_pos();
-        (x + self.raw_image.width() as i16, y + self.raw_image.height()as i16)
+        (x + self.animation.width() as i16, y + self.animation.height()as i16)
     }
-    pub fn shoot(&mut self)-> Option<Bullet>{
-        let raw_image = ImageRaw::new(BULLET_SPRITE.data, BULLET_SPRITE.width as u32, BULLET_SPRITE.height as u32);
-        if self.bullet_cool_down == 0{
-            self.bullet_cool_down = self.cool_down;
-            let x = self.x + self.raw_image.width() as i16/2 - BULLET_SPRITE.width as i16/2;
-            // if object is friendly then y = y - bullet height else y = y+bullet height;
-            let y = self.y + BULLET_SPRITE.height as i16; 
-            defmt::debug!("spawning foes bullet at ({:?}, {:?})", x, y);
-            Some(Bullet{
-                x,y,
-                friendly: false,
-                vel_y: 2, // if friendly then vel_y is -ve
-                vel_x:0,
-                raw_image,
-                active:true,
-            })
+    /// fires the enemy's pattern into bullets, aimed shots go for the nearest of targets. no
+    /// bullets while cooling down or above the screen, when bullets is full rest of the pattern
+    /// is dropped
+    pub fn shoot(&mut self, bullets:&mut Vec<Bullet, U100>, targets:&[(i16, i16)]){
+        if self.bullet_cool_down > 0 || self.y < Fx::ZERO{
+            return;
+        }
+        let (x, y) = self.get_pos();
+        let x = x + self.animation.width() as i16/2 - BULLET_SPRITE.width as i16/2;
+        // if object is friendly then y = y - bullet height else y = y+bullet height;
+        let y = y + BULLET_SPRITE.height as i16; 
+        // alternating fire takes turns between aimed shots and fans
+        let fire = match (self.fire, self.attack % 2){
+            (FirePattern::Alternating, 0) => FirePattern::Aimed,
+            (FirePattern::Alternating, _) => FirePattern::Spread,
+            (fire, _) => fire,
+        };
+        // (vel_x, vel_y) of every bullet in the pattern
+        let mut pattern:Vec<(Fx, Fx), U5> = Vec::new();
+        match fire{
+            FirePattern::Aimed => {
+                let from = (x + BULLET_SPRITE.width as i16/2, y + BULLET_SPRITE.height as i16/2);
+                let vel = match self.nearest(targets){
+                    Some(target) => aim_at(from, target, ENEMY_BULLET_SPEED),
+                    None => (Fx::ZERO, ENEMY_BULLET_SPEED),
+                };
+                pattern.push(vel).ok();
+            },
+            FirePattern::Spread => {
+                // evenly spread around straight down
+                let shots = self.shots as i16;
+                for index in 0..shots{
+                    let vel_x = Fx::from_raw(ENEMY_SPREAD.raw() * (2*index - (shots - 1)) / 2);
+                    pattern.push((vel_x, ENEMY_BULLET_SPEED)).ok();
+                }
+            },
+            // bursts are single shots too, just close together
+            _ => {
+                pattern.push((Fx::ZERO, ENEMY_BULLET_SPEED)).ok();
+            },
+        }
+        debug!("spawning foes bullets at ({:?}, {:?})", x, y);
+        for &(vel_x, vel_y) in pattern.iter(){
+            if bullets.push(Bullet::foe(x, y, vel_x, vel_y)).is_err(){
+                break;
+            }
+        }
+        self.attack = self.attack.wrapping_add(1);
+        // rest of the burst follows right away
+        self.bullet_cool_down = if self.fire == FirePattern::Burst && self.attack % self.shots != 0{
+            ENEMY_BURST_GAP
+        } else {
+            self.cool_down
+        };
+    }
+}
+
+/// velocity of about `speed` from one point towards another, straight down when they are the same
+fn aim_at(from:(i16, i16), to:(i16, i16), speed:Fx)->(Fx, Fx){
+    let (dx, dy) = ((to.0 - from.0) as i32, (to.1 - from.1) as i32);
+    // length without a square root, at most 12% too long
+    let len = dx.abs().max(dy.abs()) + dx.abs().min(dy.abs())/2;
+    if len == 0{
+        return (Fx::ZERO, speed);
+    }
+    let scale = |d:i32| Fx::from_raw((speed.raw() as i32 * d / len) as i16);
+    (scale(dx), scale(dy))
+}
+
+impl Boss{
+    pub fn new(x:i16, y:i16, sprite: &Sprite, health:u8)->Self{
+        let raw_image = ImageRaw::new(sprite.data, sprite.width as u32, sprite.height as u32);
+        debug!("spawn: Boss at ({:?}, {:?}) with health {:?}", x, y, health);
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), vel_x:Fx::ONE, raw_image, active:true, killer:0,
+            health, max_health:health, flash:0,
+            ticks:0, attack:0, bullet_cool_down:30,
+        }
+    }
+    pub fn update(&mut self, screen:&Screen) {
+        self.ticks = self.ticks.wrapping_add(1);
+        self.flash = self.flash.saturating_sub(1);
+        if self.bullet_cool_down > 0{
+            self.bullet_cool_down -=1;
+        }
+        // fly in from the top before doing anything else
+        if self.y < Fx::from_int(BOSS_TOP){
+            self.y += Fx::ONE;
+            return;
+        }
+        // strafe from side to side, faster and bobbing up and down when badly hurt
+        let speed = if self.is_enraged() { Fx::from_int(2) } else { Fx::ONE };
+        self.vel_x = if self.vel_x < Fx::ZERO { -speed } else { speed };
+        self.boundary_check(screen);
+        self.x += self.vel_x;
+        if self.is_enraged(){
+            self.y = Fx::from_int(BOSS_TOP + [0, 1, 2, 1][(self.ticks / 8 % 4) as usize]);
+        }
+    }
+    fn boundary_check(&mut self, screen:&Screen){
+        let new_pos = (self.x + self.vel_x).to_int();
+        if new_pos <= 1 || new_pos + self.raw_image.width() as i16 >= screen.width() as i16{
+            self.vel_x = -self.vel_x;
+        }
+    }
+    /// below half health boss moves and fires faster
+    pub fn is_enraged(&self)->bool{
+        self.health * 2 < self.max_health
+    }
+    pub fn health(&self)->u8{
+        self.health
+    }
+    /// takes damage, boss is dead once health is over
+    pub fn damage(&mut self, damage:u8){
+        self.health = self.health.saturating_sub(damage);
+        if self.health == 0{
+            self.active = false;
         } else {
-            None
+            self.flash = HIT_FLASH_TICKS;
         }
     }
+    pub fn get_corner_pos(&self)->(i16, i16){
+        let (x, y) = self.get_pos();
+        (x + self.raw_image.width() as i16, y + self.raw_image.height()as i16)
+    }
+    /// fires the next attack pattern into bullets, no bullets while cooling down or flying in.
+    /// when bullets is full rest of the pattern is dropped
+    pub fn shoot(&mut self, bullets:&mut Vec<Bullet, U100>){
+        let (x, y) = self.get_pos();
+        if self.bullet_cool_down > 0 || y < BOSS_TOP{
+            return;
+        }
+        let width = self.raw_image.width() as i16;
+        let y = y + self.raw_image.height() as i16;
+        let center = x + width/2 - BULLET_SPRITE.width as i16/2;
+        // (x, vel_x, vel_y) of every bullet in the pattern
+        let mut pattern:Vec<(i16, Fx, Fx), U5> = Vec::new();
+        match self.attack % 2{
+            // fan from the nose, wider when enraged
+            0 => {
+                let spread:&[i16] = if self.is_enraged() { &[-2, -1, 0, 1, 2] } else { &[-1, 0, 1] };
+                for &vel_x in spread{
+                    pattern.push((center, Fx::from_int(vel_x), Fx::from_int(2))).ok();
+                }
+            },
+            // twin shots from the wings
+            _ => {
+                pattern.push((x + 3, Fx::ZERO, Fx::from_int(3))).ok();
+                pattern.push((x + width - 3 - BULLET_SPRITE.width as i16, Fx::ZERO, Fx::from_int(3))).ok();
+            },
+        }
+        for &(x, vel_x, vel_y) in pattern.iter(){
+            if bullets.push(Bullet::foe(x, y, vel_x, vel_y)).is_err(){
+                break;
+            }
+        }
+        self.attack = self.attack.wrapping_add(1);
+        self.bullet_cool_down = if self.is_enraged() { 25 } else { 40 };
+        debug!("boss fired pattern {:?}", self.attack);
+    }
 }
 
 impl Bullet{
 // no new function for bullet. 
 // it should be created using shoot
+    // bullet of enemies and boss
+    fn foe(x:i16, y:i16, vel_x:Fx, vel_y:Fx)->Self{
+        let raw_image = ImageRaw::new(BULLET_SPRITE.data, BULLET_SPRITE.width as u32, BULLET_SPRITE.height as u32);
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), friendly:false, vel_x, vel_y, raw_image, active:true,
+            damage:1, pierce:0, inside:false, homing:false,
+        }
+    }
+    /// damage done to whatever it hits
+    pub fn damage(&self)->u8{
+        self.damage
+    }
+    /// bullet hit a target, it stops unless it can go through
+    pub fn hit(&mut self){
+        if self.pierce > 0{
+            self.pierce -= 1;
+            self.inside = true;
+        } else {
+            self.active = false;
+        }
+    }
+    /// whether it can hit what it overlaps, a bullet going through a target hits it only once
+    pub fn can_hit(&self)->bool{
+        self.active && !self.inside
+    }
+    /// bullet overlaps no target any more and can hit the next one. targets touching each other
+    /// are gone through as one
+    pub fn clear_of_targets(&mut self){
+        self.inside = false;
+    }
+    /// homing bullets turn towards the nearest of targets ahead of them, others fly straight
+    pub fn home_in(&mut self, targets:&[(i16, i16)]){
+        if !self.homing{
+            return;
+        }
+        let (x1, y1) = self.get_pos();
+        let (x2, y2) = self.get_corner_pos();
+        let (x, y) = ((x1 + x2)/2, (y1 + y2)/2);
+        let target = targets.iter()
+            .filter(|&&(_, target_y)| target_y < y)
+            .min_by_key(|&&(target_x, target_y)| (target_x - x).abs() + (y - target_y));
+        let goal = match target{
+            Some(&(target_x, _)) => HOMING_TURN_SPEED * (target_x - x).signum(),
+            None => Fx::ZERO,
+        };
+        self.vel_x = self.vel_x.approach(goal, HOMING_STEER);
+    }
     pub fn update(&mut self, screen:&Screen) {
         self.boundary_check(&screen);
-        self.x += self.vel_x as i16;
-        self.y += self.vel_y as i16;
+        self.x += self.vel_x;
+        self.y += self.vel_y;
     }
     pub fn is_friendly(&self)->bool{
         self.friendly
@@ -255,54 +982,189 @@ impl Bullet{
         let (x, y) = self.get_pos();
         (x + self.raw_image.width() as i16, y + self.raw_image.height()as i16)
     }
+    /// bullets are gone once they are off the screen, on whichever side. aimed and homing
+    /// bullets don't always fly the way they were shot
     pub fn boundary_check(&mut self, screen:&Screen){
-        // check for players boundary check
-        let new_pos:i16  = self.y + self.vel_y as i16 ;
-        if self.friendly {
-            if new_pos < 1 {
-                self.active = false;
-            }
-        } else {
-            if new_pos > screen.height() as i16{
-                self.active = false;
-            }
+        let new_x = (self.x + self.vel_x).to_int();
+        let new_y = (self.y + self.vel_y).to_int();
+        let (width, height) = (self.raw_image.width() as i16, self.raw_image.height() as i16);
+        if new_x + width <= 0 || new_x >= screen.width() as i16
+            || new_y + height <= 0 || new_y >= screen.height() as i16{
+            self.active = false;
         }
     }
 }
 
 impl Asteroid{
-    pub fn new(x:i16, y:i16, sprites: [&Sprite;4], random_val:u32)->Self{
-        let images = [
-            ImageRaw::new(sprites[0].data, sprites[0].width as u32, sprites[0].height as u32),
-            ImageRaw::new(sprites[1].data, sprites[1].width as u32, sprites[1].height as u32),
-            ImageRaw::new(sprites[2].data, sprites[2].width as u32, sprites[2].height as u32),
-            ImageRaw::new(sprites[3].data, sprites[3].width as u32, sprites[3].height as u32),
-        ];
-        let vel_x = (random_val % 3)as i8 - 1;
-        defmt::debug!("spawn: asteroid at ({:?}, {:?})", x,y);
-        Self{ x, y, vel_x , vel_y:1, images , active:true}
+    /// asteroid falling `speed` pixels a frame, drifting sideways depending on random_val
+    pub fn new(x:i16, y:i16, size:AsteroidSize, random_val:u32, speed:Fx)->Self{
+        let spec = size.spec();
+        let animation = Animation::new(spec.sheet, ASTEROID_FRAME_TICKS, Mode::Loop)
+            .starting_at((random_val >> 8) as usize);
+        let vel_x = ASTEROID_DRIFT * ((random_val % 3)as i16 - 1);
+        debug!("spawn: asteroid at ({:?}, {:?})", x,y);
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), vel_x , vel_y:speed, size, animation, active:true,
+            health:spec.health, flash:0, killer:0}
+    }
+    pub fn size(&self)->AsteroidSize{
+        self.size
+    }
+    /// two smaller asteroids next to each other where this one is, flying apart. None for the
+    /// smallest ones. next to a wall both are moved in, so neither bounces off it right away
+    pub fn split(&self, screen:&Screen)->Option<(Asteroid, Asteroid)>{
+        let size = self.size.spec().fragment?;
+        let sheet = size.spec().sheet;
+        let width = sheet.width() as i16;
+        let (x1, y1) = self.get_pos();
+        let (x2, y2) = self.get_corner_pos();
+        let y = (y1 + y2)/2 - sheet.height() as i16/2;
+        let (left_vel, right_vel) = (self.vel_x - ASTEROID_SPLIT_DRIFT, self.vel_x + ASTEROID_SPLIT_DRIFT);
+        // see boundary_check for where they would bounce
+        let min_x = 2 - left_vel.to_int().min(0);
+        let max_x = screen.width() as i16 - 1 - 2*width - right_vel.to_int().max(0);
+        let x = ((x1 + x2)/2 - width).min(max_x).max(min_x);
+        let fragment = |x, vel_x|{
+            let mut fragment = Asteroid::new(x, y, size, 0, self.vel_y);
+            fragment.vel_x = vel_x;
+            fragment
+        };
+        Some((fragment(x, left_vel), fragment(x + width, right_vel)))
     }
     pub fn update(&mut self, screen:&Screen) {
         self.boundary_check(screen);
-        self.x += self.vel_x as i16;
-        self.y += self.vel_y as i16;
+        self.x += self.vel_x;
+        self.y += self.vel_y;
+        self.animation.tick();
+        self.flash = self.flash.saturating_sub(1);
+    }
+    pub fn health(&self)->u8{
+        self.health
+    }
+    /// takes damage, shot down once there is no health left
+    pub fn damage(&mut self, damage:u8){
+        self.health = self.health.saturating_sub(damage);
+        if self.health == 0{
+            self.active = false;
+        } else {
+            self.flash = HIT_FLASH_TICKS;
+        }
     }
     pub fn get_corner_pos(&self)->(i16, i16){
         let (x, y) = self.get_pos();
-        (x + self.images[0].width() as i16, y + self.images[0].height()as i16)
+        (x + self.animation.width() as i16, y + self.animation.height()as i16)
     }
     fn boundary_check(&mut self, screen:&Screen){
         // check for players boundary check
-        let new_pos:i16  = self.x + self.vel_x as i16;
-        if new_pos <= 1  || new_pos + self.images[0].width() as i16 >= screen.width as i16 {
+        let new_pos:i16  = (self.x + self.vel_x).to_int();
+        if new_pos <= 1  || new_pos + self.animation.width() as i16 >= screen.width as i16 {
             self.vel_x = -self.vel_x ;
         }
-        if self.y + self.vel_y as i16 >= screen.height() as i16 {
-            self.y = 1 - self.images[0].height() as i16;
+        if (self.y + self.vel_y).to_int() >= screen.height() as i16 {
+            self.y = Fx::from_int(1 - self.animation.height() as i16);
+        }
+    }
+}
+
+impl Effect{
+    /// explosion centered on given point
+    pub fn explosion(x:i16, y:i16)->Self{
+        let animation = Animation::new(&EXPLOSION_SHEET, EFFECT_FRAME_TICKS, Mode::Once);
+        let x = Fx::from_int(x - animation.width() as i16/2);
+        let y = Fx::from_int(y - animation.height() as i16/2);
+        Self{ x, y, vel_x:Fx::ZERO, vel_y:Fx::ZERO, kind:EffectKind::Explosion(animation), active:true }
+    }
+    pub fn debris(x:i16, y:i16, vel_x:Fx, vel_y:Fx)->Self{
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), vel_x, vel_y, kind:EffectKind::Debris(DEBRIS_FRAMES), active:true }
+    }
+    pub fn update(&mut self){
+        self.x += self.vel_x;
+        self.y += self.vel_y;
+        match &mut self.kind{
+            EffectKind::Explosion(animation) => {
+                animation.tick();
+                if animation.is_finished(){
+                    self.active = false;
+                }
+            },
+            EffectKind::Debris(frames) => {
+                *frames = frames.saturating_sub(1);
+                if *frames == 0{
+                    self.active = false;
+                }
+            },
+        }
+    }
+}
+
+impl Weapon{
+    pub fn spec(self)->&'static WeaponSpec{
+        match self{
+            Weapon::Single => &SINGLE_SHOT,
+            Weapon::Spread => &SPREAD_SHOT,
+            Weapon::Laser => &LASER_BEAM,
+            Weapon::Piercing => &PIERCING_SHOT,
+            Weapon::Homing => &HOMING_MISSILE,
+        }
+    }
+}
+
+impl AsteroidSize{
+    pub fn spec(self)->&'static AsteroidSpec{
+        match self{
+            AsteroidSize::Large => &LARGE_ASTEROID,
+            AsteroidSize::Medium => &MEDIUM_ASTEROID,
+            AsteroidSize::Small => &SMALL_ASTEROID,
         }
     }
 }
 
+impl PowerUpKind{
+    /// kind for a random value, weighted by POWER_UP_DROPS
+    pub fn pick(random_val:u32)->Self{
+        let total:u32 = POWER_UP_DROPS.iter().map(|&(_, weight)| weight as u32).sum();
+        let mut roll = random_val % total;
+        for &(kind, weight) in POWER_UP_DROPS.iter(){
+            if roll < weight as u32{
+                return kind;
+            }
+            roll -= weight as u32;
+        }
+        POWER_UP_DROPS[0].0
+    }
+    pub fn sprite(self)->&'static Sprite{
+        match self{
+            PowerUpKind::RapidFire => &RAPID_FIRE_ICON,
+            PowerUpKind::TripleShot => &TRIPLE_SHOT_ICON,
+            PowerUpKind::Shield => &SHIELD_ICON,
+            PowerUpKind::ExtraLife => &EXTRA_LIFE_ICON,
+            PowerUpKind::Bomb => &BOMB_ICON,
+            PowerUpKind::Weapon(weapon) => weapon.spec().icon,
+        }
+    }
+}
+
+impl PowerUp{
+    /// power-up centered on given point
+    pub fn new(x:i16, y:i16, kind:PowerUpKind)->Self{
+        let sprite = kind.sprite();
+        let x = x - sprite.width() as i16/2;
+        let y = y - sprite.height() as i16/2;
+        debug!("spawn: power-up at ({:?}, {:?})", x, y);
+        Self{ x:Fx::from_int(x), y:Fx::from_int(y), kind, active:true }
+    }
+    pub fn update(&mut self, screen:&Screen){
+        self.y += POWER_UP_SPEED;
+        if self.y.to_int() > screen.height() as i16{
+            self.active = false;
+        }
+    }
+    pub fn get_corner_pos(&self)->(i16, i16){
+        let (x, y) = self.get_pos();
+        let sprite = self.kind.sprite();
+        (x + sprite.width() as i16, y + sprite.height() as i16)
+    }
+}
+
 impl Sprite{
     pub fn height(&self)->u8{
         self.height
@@ -313,21 +1175,28 @@ impl Sprite{
 }
 
 impl Stats{
-    pub fn new(screen: &Screen)->Self{
+    /// stats bar with a row for each player
+    pub fn new(screen: &Screen, players:u8)->Self{
         let border = Rectangle::new(
-            Point::new(0,screen.height() as i32 +1), Point::new(screen.width as i32 + 1, screen.height() as i32 + 12)
+            Point::new(0,screen.height() as i32 +1), Point::new(screen.width as i32 + 1, screen.height() as i32 + Self::height(players) as i32)
             )
             .into_styled(
                 PrimitiveStyle::with_stroke(BinaryColor::On, 1)
                 );
         let score = ImageRaw::new(GUN.data, GUN.width.into(), GUN.height.into());
-        let ammo = ImageRaw::new(AMMO.data, AMMO.width.into(), AMMO.height.into());
+        let lives = ImageRaw::new(HEART.data, HEART.width.into(), HEART.height.into());
+        let ammo = ImageRaw::new(BULLET_SPRITE.data, BULLET_SPRITE.width.into(), BULLET_SPRITE.height.into());
         Self{
             border,
             score,
+            lives,
             ammo,
         }
     }
+    /// pixels taken from the bottom of display
+    pub fn height(players:u8)->u8{
+        2 + LEVEL_ROW_HEIGHT + STATS_ROW_HEIGHT * players
+    }
 }
 
 // these implementations are later changed into macros, currently for simplicity it is implimented
@@ -340,7 +1209,8 @@ impl Object for Player {
         self.active
     }
     fn bury(self, _:&mut i16){
-        todo!()
+        // player stays for the whole game, lives and respawn are handled by GamePool::collect
+        debug!("player buried with score: {:?}", self.player_score);
     }
 
 
@@ -351,8 +1221,19 @@ impl Object for Enemy {
         self.active
     }
     fn bury(self, score: &mut i16){
-        defmt::debug!("player score: {:?}", *score);
-        *score +=1;
+        // tougher enemies are worth more
+        *score += self.max_health as i16;
+        debug!("player score: {:?}", *score);
+    }
+}
+
+impl Object for Boss {
+    fn is_active(&self) ->bool {
+        self.active
+    }
+    fn bury(self, score: &mut i16){
+        *score += BOSS_SCORE;
+        debug!("boss defeated, player score: {:?}", *score);
     }
 }
 
@@ -364,84 +1245,221 @@ impl Object for Bullet {
     }
 }
 
+impl Object for Effect {
+    fn is_active(&self) ->bool {
+        self.active
+    }
+    fn bury(self, _:&mut i16) {
+    }
+}
+
+impl Object for PowerUp {
+    fn is_active(&self) ->bool {
+        self.active
+    }
+    fn bury(self, _:&mut i16) {
+    }
+}
+
 impl Object for Asteroid {
     fn is_active(&self) ->bool {
         self.active
     }
 
     fn bury(self, score:&mut i16) {
-        *score +=1;
-        defmt::debug!("player score: {:?}", *score);
+        *score += self.size.spec().score;
+        debug!("player score: {:?}", *score);
     }
 }
 
 
 impl Movable for Player{
     fn get_pos(&self) ->(i16, i16) {
-        (self.x, self.y)
+        (self.x.to_int(), self.y.to_int())
     }
     fn set_pos(&mut self, x:i16, y:i16) {
-        self.x = x;
-        self.y = y;
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
     }
 }
-// although enemy cant move, it can be replaced
+// moving enemy moves its home along, so that it doesn't fly back
 impl Movable for Enemy{
     fn get_pos(&self) ->(i16, i16) {
-        (self.x, self.y)
+        (self.x.to_int(), self.y.to_int())
+    }
+    fn set_pos(&mut self, x:i16, y:i16) {
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
+        self.home_x = self.x;
+        self.home_y = self.y;
+    }
+}
+impl Movable for Boss{
+    fn get_pos(&self) ->(i16, i16) {
+        (self.x.to_int(), self.y.to_int())
     }
     fn set_pos(&mut self, x:i16, y:i16) {
-        self.x = x;
-        self.y = y;
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
     }
 }
 impl Movable for Bullet{
     fn get_pos(&self) ->(i16, i16) {
-        (self.x, self.y)
+        (self.x.to_int(), self.y.to_int())
     }
     fn set_pos(&mut self, x:i16, y:i16) {
-        self.x = x;
-        self.y = y;
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
+    }
+}
+impl Movable for Effect{
+    fn get_pos(&self) ->(i16, i16) {
+        (self.x.to_int(), self.y.to_int())
+    }
+    fn set_pos(&mut self, x:i16, y:i16) {
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
+    }
+}
+impl Movable for PowerUp{
+    fn get_pos(&self) ->(i16, i16) {
+        (self.x.to_int(), self.y.to_int())
+    }
+    fn set_pos(&mut self, x:i16, y:i16) {
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
     }
 }
 impl Movable for Asteroid{
     fn get_pos(&self) ->(i16, i16) {
-        (self.x, self.y)
+        (self.x.to_int(), self.y.to_int())
     }
     fn set_pos(&mut self, x:i16, y:i16) {
-        self.x = x;
-        self.y = y;
+        self.x = Fx::from_int(x);
+        self.y = Fx::from_int(y);
     }
 }
 
 impl CanDraw for Player{
-    fn draw(&self, disp:&mut Display) {
-        let image = Image::new( &self.raw_image, Point::new(self.x as i32, self.y as i32) );
-        image.draw(disp).unwrap();
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        let (x, y) = self.get_pos();
+        if !self.active{
+            if !self.is_dying(){
+                return Ok(());
+            }
+            let raw_image = self.explosion.image();
+            return Image::new( &raw_image, Point::new(x as i32, y as i32) ).draw(disp);
+        }
+        // blink every other frame while invulnerable
+        if self.invulnerable % 2 == 1{
+            return Ok(());
+        }
+        let image = Image::new( &self.raw_image, Point::new(x as i32, y as i32) );
+        image.draw(disp)?;
+        // shield around the ship, blinking when about to run out
+        if self.shield > POWER_UP_WARNING_TICKS || (self.shield > 0 && self.shield / 4 % 2 == 0){
+            let (x2, y2) = self.get_corner_pos();
+            let center = Point::new((x + x2) as i32/2, (y + y2) as i32/2);
+            Circle::new(center, self.raw_image.width()/2 + 2)
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                .draw(disp)?;
+        }
+        // engine flickers right under the ship
+        let engine = self.engine.image();
+        let x = x + self.raw_image.width() as i16/2 - self.engine.width() as i16/2;
+        let y = y + self.raw_image.height() as i16;
+        Image::new( &engine, Point::new(x as i32, y as i32) ).draw(disp)
     }
 }
 impl CanDraw for Bullet{
-    fn draw(&self, disp:&mut Display) {
-        let image = Image::new( &self.raw_image, Point::new(self.x as i32, self.y as i32) );
-        image.draw(disp).unwrap();
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        let (x, y) = self.get_pos();
+        let image = Image::new( &self.raw_image, Point::new(x as i32, y as i32) );
+        image.draw(disp)
     }
 }
 impl CanDraw for Enemy{
-    fn draw(&self, disp:&mut Display) {
-        let image = Image::new( &self.raw_image, Point::new(self.x as i32, self.y as i32) );
-        image.draw(disp).unwrap();
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        // blinks after a hit
+        if self.flash % 2 == 1{
+            return Ok(());
+        }
+        let (x, y) = self.get_pos();
+        let raw_image = self.animation.image();
+        let image = Image::new( &raw_image, Point::new(x as i32, y as i32) );
+        image.draw(disp)?;
+        // armoured ones show how much is left of them
+        if self.max_health > 1{
+            let filled = self.animation.width() as i32 * self.health as i32 / self.max_health as i32;
+            Rectangle::new(Point::new(x as i32, y as i32 - 2), Point::new(x as i32 + filled - 1, y as i32 - 2))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(disp)?;
+        }
+        Ok(())
+    }
+}
+impl CanDraw for Boss{
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        let (x, y) = self.get_pos();
+        let (x, y) = (x as i32, y as i32);
+        // blinks after a hit, health bar stays
+        if self.flash % 2 == 0{
+            let image = Image::new( &self.raw_image, Point::new(x, y) );
+            image.draw(disp)?;
+        }
+        // health bar right above the boss
+        let width = self.raw_image.width() as i32;
+        let filled = width * self.health as i32 / self.max_health.max(1) as i32;
+        let top = y - 4;
+        Rectangle::new(Point::new(x, top), Point::new(x + width - 1, top + 2))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(disp)?;
+        if filled > 0{
+            Rectangle::new(Point::new(x, top), Point::new(x + filled - 1, top + 2))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(disp)?;
+        }
+        Ok(())
     }
 }
 impl CanDraw for Asteroid{
-    fn draw(&self, disp:&mut Display) {
-        let index = self.y as usize%4;
-        let raw_image:ImageRaw<BinaryColor> = self.images[index];
-        let image = Image::new( &raw_image, Point::new(self.x as i32, self.y as i32) ) ;
-        image.draw(disp).unwrap();
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        // blinks after a hit
+        if self.flash % 2 == 1{
+            return Ok(());
+        }
+        let (x, y) = self.get_pos();
+        let raw_image = self.animation.image();
+        let image = Image::new( &raw_image, Point::new(x as i32, y as i32) ) ;
+        image.draw(disp)
+    }
+}
+impl CanDraw for Effect{
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        if !self.active{
+            return Ok(());
+        }
+        let (x, y) = self.get_pos();
+        let point = Point::new(x as i32, y as i32);
+        match &self.kind{
+            EffectKind::Explosion(animation) => {
+                let raw_image = animation.image();
+                Image::new( &raw_image, point ).draw(disp)
+            },
+            EffectKind::Debris(_) => Pixel(point, BinaryColor::On).draw(disp),
+        }
+    }
+}
+impl CanDraw for PowerUp{
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        let (x, y) = self.get_pos();
+        let sprite = self.kind.sprite();
+        let raw_image:ImageRaw<BinaryColor> = ImageRaw::new(sprite.data, sprite.width as u32, sprite.height as u32);
+        Image::new( &raw_image, Point::new(x as i32, y as i32) ).draw(disp)
     }
 }
 impl CanDraw for Screen{
-    fn draw(&self, disp:&mut Display) {
-        self.border.draw(disp).unwrap();
+    fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error> {
+        self.border.draw(disp)
     }
 }
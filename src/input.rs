@@ -0,0 +1,70 @@
+/// Anything the player can be steered with.
+///
+/// On the board it is implemented for the left/right push buttons, on the host it can be backed
+/// by a script or a recording.
+pub trait Input{
+    /// true while left is held down
+    fn left(&self)->bool;
+    /// true while right is held down
+    fn right(&self)->bool;
+}
+
+/// Buttons sampled once per frame, packed into a single byte.
+///
+/// left and right are levels (held down during the frame), shoot and pause are events
+/// (pressed during the frame), the same way the board sees them through its interrupts.
+/// buttons of the second player sit in the high nibble, pause is shared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputFrame(u8);
+
+impl InputFrame{
+    pub const LEFT:u8   = 1 << 0;
+    pub const RIGHT:u8  = 1 << 1;
+    pub const SHOOT:u8  = 1 << 2;
+    pub const PAUSE:u8  = 1 << 3;
+    pub const LEFT_2:u8 = Self::LEFT << 4;
+    pub const RIGHT_2:u8= Self::RIGHT << 4;
+    pub const SHOOT_2:u8= Self::SHOOT << 4;
+    const PLAYER:u8     = Self::LEFT | Self::RIGHT | Self::SHOOT;
+    const MASK:u8       = Self::PLAYER | Self::PAUSE | (Self::PLAYER << 4);
+
+    pub fn new(left:bool, right:bool, shoot:bool, pause:bool)->Self{
+        let mut bits = 0;
+        if left { bits |= Self::LEFT; }
+        if right { bits |= Self::RIGHT; }
+        if shoot { bits |= Self::SHOOT; }
+        if pause { bits |= Self::PAUSE; }
+        Self(bits)
+    }
+    /// adds buttons of the second player
+    pub fn with_player_2(self, left:bool, right:bool, shoot:bool)->Self{
+        let second = Self::new(left, right, shoot, false);
+        Self(self.0 | second.0 << 4)
+    }
+    /// buttons of given player as if it were the only one, without pause
+    pub fn player(&self, index:u8)->Self{
+        Self((self.0 >> (4 * index)) & Self::PLAYER)
+    }
+    /// unknown bits are dropped
+    pub const fn from_bits(bits:u8)->Self{
+        Self(bits & Self::MASK)
+    }
+    pub fn bits(&self)->u8{
+        self.0
+    }
+    pub fn shoot(&self)->bool{
+        self.0 & Self::SHOOT != 0
+    }
+    pub fn pause(&self)->bool{
+        self.0 & Self::PAUSE != 0
+    }
+}
+
+impl Input for InputFrame{
+    fn left(&self)->bool{
+        self.0 & Self::LEFT != 0
+    }
+    fn right(&self)->bool{
+        self.0 & Self::RIGHT != 0
+    }
+}
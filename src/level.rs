@@ -0,0 +1,245 @@
+// Levels and waves. every level is a list of waves of enemies, the next wave comes once the last
+// one is shot down, and some levels end with a boss. asteroids fly around all level long.
+//
+// levels are described in assets/levels.toml, which build.rs checks and turns into LEVELS.
+// once the last one is cleared it is played again and again.
+use crate::{
+    fixed::Fx,
+    game::EnemyKind,
+    objects::{LEVELS, LEVEL_BANNER_TICKS},
+};
+
+/// Enemy of a wave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaveEnemy{
+    pub kind: EnemyKind,
+    /// where it spawns, somewhere random when None. formations only use y
+    pub pos: Option<(i16, i16)>,
+    /// ticks it comes after the enemy before it, or after the wave started
+    pub delay: u16,
+}
+
+/// Enemies coming together, spawned one after another in this order.
+#[derive(Debug)]
+pub struct Wave{
+    pub enemies: &'static [WaveEnemy],
+}
+
+#[derive(Debug)]
+pub struct Level{
+    pub waves: &'static [Wave],
+    /// asteroids flying around during the level
+    pub asteroids: u8,
+    /// pixels asteroids fall a tick
+    pub asteroid_speed: Fx,
+    /// ticks between shots of every enemy
+    pub enemy_cool_down: u16,
+    /// percent of kills dropping a power-up
+    pub power_up_chance: u8,
+    /// ticks for players to get a round back, never when 0
+    pub ammo_refill: u16,
+    /// boss comes after the last wave
+    pub boss: bool,
+}
+
+/// what to spawn next, see `Progress::tick`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spawn{
+    Nothing,
+    Enemy(WaveEnemy),
+    Boss,
+}
+
+/// Where the game is in LEVELS.
+#[derive(Debug)]
+pub struct Progress{
+    // index into LEVELS, keeps counting after the last one
+    level: u8,
+    wave: u8,
+    // enemies of the wave spawned so far
+    spawned: u8,
+    // ticks since last enemy of the wave spawned
+    spawn_timer: u16,
+    // boss of the level has shown up
+    boss: bool,
+    // ticks left of the "Level N" banner
+    banner: u16,
+}
+
+impl Progress{
+    /// first level, starting with its banner
+    pub fn new()->Self{
+        Self{ level: 0, wave: 0, spawned: 0, spawn_timer: 0, boss: false, banner: LEVEL_BANNER_TICKS }
+    }
+    /// tuning of the current level
+    pub fn level(&self)->&'static Level{
+        &LEVELS[(self.level as usize).min(LEVELS.len() - 1)]
+    }
+    /// level shown to the player, 1 for the first one
+    pub fn number(&self)->u8{
+        self.level.saturating_add(1)
+    }
+    /// wave of the level, 0 for the first one
+    pub fn wave(&self)->u8{
+        self.wave
+    }
+    /// true while the level banner is up, nothing but asteroids spawn meanwhile
+    pub fn show_banner(&self)->bool{
+        self.banner > 0
+    }
+
+    /// to be called every tick, returns what to spawn. `clear` tells if all enemies and boss
+    /// are gone, `room` how many more enemies fit on screen
+    pub fn tick(&mut self, clear:bool, room:usize)->Spawn{
+        if self.banner > 0{
+            self.banner -= 1;
+            return Spawn::Nothing;
+        }
+        let level = self.level();
+        if let Some(wave) = level.waves.get(self.wave as usize){
+            match wave.enemies.get(self.spawned as usize){
+                Some(&enemy) => {
+                    // not yet, or wait for some of them to be shot down
+                    if self.spawn_timer < enemy.delay{
+                        self.spawn_timer += 1;
+                        return Spawn::Nothing;
+                    }
+                    if enemy.kind.size() > room{
+                        return Spawn::Nothing;
+                    }
+                    self.spawned += 1;
+                    self.spawn_timer = 0;
+                    return Spawn::Enemy(enemy);
+                },
+                // whole wave is out, next one once it is shot down
+                None => if clear{
+                    self.wave += 1;
+                    self.spawned = 0;
+                    self.spawn_timer = 0;
+                    debug!("wave {:?} of level {:?}", self.wave, self.number());
+                },
+            }
+            return Spawn::Nothing;
+        }
+        if level.boss && !self.boss{
+            self.boss = true;
+            return Spawn::Boss;
+        }
+        if clear{
+            self.next_level();
+        }
+        Spawn::Nothing
+    }
+
+    fn next_level(&mut self){
+        self.level = self.level.saturating_add(1);
+        self.wave = 0;
+        self.spawned = 0;
+        self.spawn_timer = 0;
+        self.boss = false;
+        self.banner = LEVEL_BANNER_TICKS;
+        debug!("level {:?}", self.number());
+    }
+}
+
+impl Default for Progress{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ticks with everything shot down right away till the level is over, returns the spawns
+    fn play_level(progress:&mut Progress)->([Option<WaveEnemy>; 16], usize, bool){
+        let number = progress.number();
+        let mut enemies = [None; 16];
+        let (mut count, mut boss) = (0, false);
+        while progress.number() == number{
+            match progress.tick(true, 10){
+                Spawn::Enemy(enemy) => {
+                    enemies[count] = Some(enemy);
+                    count += 1;
+                },
+                Spawn::Boss => boss = true,
+                Spawn::Nothing => {},
+            }
+        }
+        (enemies, count, boss)
+    }
+
+    #[test]
+    fn banner_comes_first(){
+        let mut progress = Progress::new();
+        for _ in 0..LEVEL_BANNER_TICKS{
+            assert!(progress.show_banner());
+            assert_eq!(progress.tick(true, 10), Spawn::Nothing);
+        }
+        assert!(!progress.show_banner());
+        // first enemy comes after its delay
+        let delay = LEVELS[0].waves[0].enemies[0].delay;
+        for _ in 0..delay{
+            assert_eq!(progress.tick(true, 10), Spawn::Nothing);
+        }
+        assert_eq!(progress.tick(true, 10), Spawn::Enemy(LEVELS[0].waves[0].enemies[0]));
+    }
+
+    #[test]
+    fn waves_spawn_in_order(){
+        let mut progress = Progress::new();
+        let (enemies, count, boss) = play_level(&mut progress);
+        let mut expected = LEVELS[0].waves.iter().flat_map(|wave| wave.enemies.iter().copied());
+        assert!(enemies[..count].iter().all(|&enemy| enemy == expected.next()));
+        assert!(expected.next().is_none());
+        assert_eq!(boss, LEVELS[0].boss);
+        assert_eq!(progress.number(), 2);
+        assert!(progress.show_banner());
+    }
+
+    #[test]
+    fn next_wave_waits_for_the_last_one(){
+        let mut progress = Progress::new();
+        let first = LEVELS[0].waves[0].enemies.len();
+        let mut spawned = 0;
+        for _ in 0..1000{
+            if let Spawn::Enemy(_) = progress.tick(false, 10){
+                spawned += 1;
+            }
+        }
+        assert_eq!(spawned, first);
+        assert_eq!(progress.wave(), 0);
+        // no room at all
+        let mut progress = Progress::new();
+        for _ in 0..1000{
+            assert_eq!(progress.tick(true, 0), Spawn::Nothing);
+        }
+    }
+
+    #[test]
+    fn boss_holds_up_the_level(){
+        let mut progress = Progress::new();
+        while !progress.level().boss{
+            play_level(&mut progress);
+        }
+        let number = progress.number();
+        while progress.tick(true, 10) != Spawn::Boss{}
+        for _ in 0..1000{
+            assert_eq!(progress.tick(false, 10), Spawn::Nothing);
+        }
+        assert_eq!(progress.number(), number);
+        progress.tick(true, 10);
+        assert_eq!(progress.number(), number + 1);
+    }
+
+    #[test]
+    fn last_level_repeats(){
+        let mut progress = Progress::new();
+        for _ in 0..LEVELS.len() + 2{
+            play_level(&mut progress);
+        }
+        assert_eq!(progress.number() as usize, LEVELS.len() + 3);
+        assert!(core::ptr::eq(progress.level(), &LEVELS[LEVELS.len() - 1]));
+    }
+}
@@ -1,10 +1,41 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(const_in_array_repeat_expressions)]
 
+// logging goes through defmt on the board, on the host it is compiled out
+#[cfg(feature = "board")]
+macro_rules! debug {
+    ($($arg:tt)*) => { defmt::debug!($($arg)*) };
+}
+#[cfg(not(feature = "board"))]
+macro_rules! debug {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {{ $( let _ = &$arg; )* }};
+}
+#[cfg(feature = "board")]
+macro_rules! info {
+    ($($arg:tt)*) => { defmt::info!($($arg)*) };
+}
+#[cfg(not(feature = "board"))]
+macro_rules! info {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {{ $( let _ = &$arg; )* }};
+}
+
 // import Section 
-pub mod types;
 pub mod game;
+pub mod fixed;
+pub mod level;
+pub mod animation;
 pub mod objects;
+pub mod input;
+pub mod rng;
+pub mod timing;
+pub mod profiler;
+pub mod framebuffer;
+pub mod replay;
+pub mod scene;
+#[cfg(feature = "board")]
+pub mod types;
+#[cfg(feature = "board")]
+pub mod board;
 
 use heapless::{
     Vec,
@@ -12,15 +43,15 @@ use heapless::{
     consts::*,
 };
 
-use core:: sync::atomic::{AtomicUsize, Ordering};
-// use core::panic::PanicInfo;
-
 use game::* ;
 use objects::*;
-use types::*;
+use input::InputFrame;
+use rng::GameRng;
+use profiler::{Phase, Probe};
+use level::{Progress, Spawn, WaveEnemy};
 
-use defmt_rtt as _; // global logger
-use panic_probe as _;
+#[cfg(feature = "board")]
+pub use board::exit;
 
 use embedded_graphics::{
     prelude::*,
@@ -31,27 +62,48 @@ use embedded_graphics::{
     style::{PrimitiveStyle, TextStyle},
 };
 
-use stm32f7xx_hal::prelude::*;
+// the levels are checked against these, see src/limits.rs
+const _: () = assert!(<U20 as typenum::Unsigned>::USIZE == MAX_ASTEROIDS);
+const _: () = assert!(<U10 as typenum::Unsigned>::USIZE == MAX_WAVE_ENEMIES);
 
 // structs 
 
 #[derive(Debug)]
 pub struct GamePool{
-    pub player: Player,
+    pub players: Vec<Player, U2>,
     enemies: Vec<Enemy, U10>,
     bullets:Vec<Bullet, U100>,
     asteroids: Vec<Asteroid, U20>,
+    effects: Vec<Effect, U32>,
+    power_ups: Vec<PowerUp, U4>,
+    // where things were shot down this tick, power-ups may drop there
+    drops: Vec<(i16, i16), U8>,
+    boss: Option<Boss>,
+    // number of bosses spawned so far
+    bosses: u8,
+    progress: Progress,
     screen: Screen,
     stats: Stats,
     status: bool,
     fps : u16,
-    fps_ctr: u16,
 }
 impl GamePool{
     // This will return all necessory game objects
-    pub fn init(disp:&Display)->Self{
-        let (disp_width, disp_height )= disp.get_dimensions();
-        let disp_height = disp_height - 12;
+    pub fn init<D: DrawTarget<BinaryColor>>(disp:&D)->Self{
+        Self::new(disp.size())
+    }
+
+    /// same as init, but for a display of given size
+    pub fn new(size:Size)->Self{
+        Self::with_players(size, 1)
+    }
+
+    /// game for 1 or 2 players sharing the screen
+    pub fn with_players(size:Size, players:u8)->Self{
+        let players = players.clamp(1, MAX_PLAYERS);
+        let Size{width:disp_width, height:disp_height} = size;
+        let disp_width = disp_width as u8;
+        let disp_height = disp_height as u8 - Stats::height(players);
         let border = Rectangle::new(
             Point::zero(), Point::new( (disp_width - 1 ) as i32, (disp_height - 1) as i32))
             .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1));
@@ -62,121 +114,274 @@ impl GamePool{
             disp_height -2,
             border,
         );
-        // start the player in center 
-        let player = Player::new(
-            (screen.width()/2 - PLAYER_1_SPRITE.width()/2 +1)as i16, 
-            (screen.height() - PLAYER_1_SPRITE.height() - 1) as i16, // -1 for border
-            &PLAYER_1_SPRITE
-        );
+        // players start next to each other at the bottom
+        let mut player_list:Vec<Player, U2> = Vec::new();
+        for index in 0..players{
+            let (x, y) = Self::player_start(&screen, index, players);
+            player_list.push(Player::new(x, y, PLAYER_SPRITES[index as usize])).ok();
+        }
         // enemies
         let enemies:Vec<Enemy, U10> = Vec::new();
         let bullets:Vec<Bullet, U100> = Vec::new();
         let asteroids:Vec<Asteroid, U20> = Vec::new();
-        let stats = Stats::new(&screen);
-        Self{player, enemies, bullets, asteroids, screen, stats, status:true, fps_ctr:0, fps:0}
+        let effects:Vec<Effect, U32> = Vec::new();
+        let stats = Stats::new(&screen, players);
+        Self{players:player_list, enemies, bullets, asteroids, effects, power_ups:Vec::new(), drops:Vec::new(), boss:None, bosses:0, progress:Progress::new(), screen, stats, status:true, fps:0}
+    }
+
+    /// bottom of the screen, where player starts and respawns. single player starts in center,
+    /// two players at one and two thirds of the screen
+    fn player_start(screen:&Screen, index:u8, players:u8)->(i16, i16){
+        let sprite = PLAYER_SPRITES[index as usize];
+        (
+            (screen.width() as u16 * (index as u16 + 1) / (players as u16 + 1)) as i16 - sprite.width() as i16/2 + 1,
+            (screen.height() - sprite.height() - 1) as i16, // -1 for border
+        )
+    }
+
+    /// score of all players together
+    pub fn score(&self)->i16{
+        self.players.iter().map(|player| player.player_score).sum()
+    }
+
+    /// advances the game by one frame with the buttons sampled for that frame
+    pub fn tick<R: GameRng>(&mut self, input:InputFrame, rng:&mut R){
+        self.tick_probed(input, rng, &mut ());
+    }
+
+    /// same as tick, with every phase run through the probe
+    pub fn tick_probed<R: GameRng, P: Probe>(&mut self, input:InputFrame, rng:&mut R, probe:&mut P){
+        for index in 0..self.players.len(){
+            if input.player(index as u8).shoot() && self.players[index].can_shoot(){
+                self.players[index].shoot();
+            }
+        }
+        probe.measure(Phase::Spawn, || self.spawn(rng));
+        probe.measure(Phase::Update, || self.update(&input));
+        probe.measure(Phase::Collect, || self.collect());
     }
 
     /// spawns objects like enemies and asteroids, but not bullets
-    pub fn spawn(&mut self, rng:&mut stm32f7xx_hal::rng::Rng) {
-        // spawn asteroids
-        while self.asteroids.len() as i16 <= self.player.player_score/LEVEL_SCORE as i16{
+    pub fn spawn<R: GameRng>(&mut self, rng:&mut R) {
+        let level = self.progress.level();
+        let number = self.progress.number();
+        // keep the asteroids of the level flying
+        while self.asteroids.len() < level.asteroids as usize{
             // get random value for spawn position
-            let random_val = match rng.get_rand(){
-                Ok(val) => val,
-                Err(_) => {
-                    defmt::warn!("couldn't generate random value for asteroid. spawning will be corner");
-                    0
-                },
-            };
-            let x_pos = random_val% (self.screen.width() - &ASTEROID_SPRITE_1.width) as u32;
+            let random_val = rng.next_u32();
+            let size = ASTEROID_SIZES[(random_val >> 24) as usize % ASTEROID_SIZES.len()];
+            let sheet = size.spec().sheet;
+            let x_pos = random_val% (self.screen.width() - sheet.width()) as u32;
             // spawn asteroid
             let asteroid = Asteroid::new(
                 x_pos as i16,
-                1 - ASTEROID_SPRITE_1.height as i16,
-                [&ASTEROID_SPRITE_1, &ASTEROID_SPRITE_2, &ASTEROID_SPRITE_3, &ASTEROID_SPRITE_4],
-                random_val
+                1 - sheet.height() as i16,
+                size,
+                random_val,
+                level.asteroid_speed,
                 );
             self.asteroids.push(
                 asteroid
                 ).expect("couldn't create enemy");
         }
-        // spawn enemies
-        while self.enemies.len() as i16 <= self.player.player_score/(LEVEL_SCORE*2) as i16{
-            let rand_val:u32 = match rng.get_rand(){
-                Ok(val) => val,
-                Err(_)=>{
-                    // if cant generate random value then spawn in center
-                    defmt::warn!("couldn't generate random value for enemies. spawning will be center");
-                    (self.screen.width() as u32 / 2)<< 16 | (self.screen.height()as u32 / 2)
+        // enemies of the wave one by one, boss after the last wave of some levels
+        let clear = self.enemies.is_empty() && self.boss.is_none();
+        let room = self.enemies.capacity() - self.enemies.len();
+        match self.progress.tick(clear, room){
+            Spawn::Enemy(WaveEnemy{ kind, pos, .. }) => {
+                let rand_val:u32 = rng.next_u32();
+                // let xpos:i16 = (self.screen.width()/2 -&ENEMY_SPRITE.width()/2 -1 ) as i16;
+                let xpos:i16 = ((rand_val >> 16 ) as u16 % (self.screen.width() - &ENEMY_SPRITE.width) as u16 + 1) as i16;
+                let ypos:i16 = (rand_val as u16 % (self.screen.width() - &ENEMY_SPRITE.width) as u16 + 1) as i16;
+                // unless the level says where
+                let (xpos, ypos) = pos.unwrap_or((xpos, ypos));
+                if kind.behaviour == Behaviour::Formation{
+                    self.spawn_formation(kind, ypos, level.enemy_cool_down, number, rand_val);
+                } else {
+                    let enemy = Enemy::new(xpos, ypos , &ENEMY_SHEET, level.enemy_cool_down).of_kind(kind, number);
+                    self.enemies.push(
+                        enemy
+                        ).expect("couldn't create enemy");
                 }
-            };
-            // let xpos:i16 = (self.screen.width()/2 -&ENEMY_SPRITE.width()/2 -1 ) as i16;
-            let xpos:i16 = ((rand_val >> 16 ) as u16 % (self.screen.width() - &ENEMY_SPRITE.width) as u16 + 1) as i16;
-            let ypos:i16 = (rand_val as u16 % (self.screen.width() - &ENEMY_SPRITE.width) as u16 + 1) as i16;
-            let cooldown = LEVEL_SCORE as i16 - self.player.player_score % LEVEL_SCORE  as i16;
-            let enemy = Enemy::new(xpos, ypos , &ENEMY_SPRITE, cooldown as u16);
-            self.enemies.push(
-                enemy
-                ).expect("couldn't create enemy");
+            },
+            Spawn::Boss => {
+                let x = (self.screen.width() - BOSS_SPRITE.width()) as i16 / 2 + 1;
+                let health = BOSS_HEALTH.saturating_add(BOSS_HEALTH_PER_LEVEL.saturating_mul(self.bosses));
+                self.boss = Some(Boss::new(x, 1 - BOSS_SPRITE.height() as i16, &BOSS_SPRITE, health));
+                self.bosses += 1;
+            },
+            Spawn::Nothing => {},
+        }
+        // some kills drop a power-up
+        for index in 0..self.drops.len(){
+            let random_val = rng.next_u32();
+            if random_val % 100 < level.power_up_chance as u32{
+                let (x, y) = self.drops[index];
+                self.power_ups.push(PowerUp::new(x, y, PowerUpKind::pick(random_val / 100))).ok();
+            }
         }
+        self.drops.clear();
         // spawn enemy bullets
+        let targets = self.targets();
         for i in 0..self.enemies.len(){
-            if self.enemies[i].bullet_cool_down == 0{
-                if let Some(bullet) = self.enemies[i].shoot(){
-                    self.bullets.push(bullet).unwrap();
-                }
-            }
+            self.enemies[i].shoot(&mut self.bullets, &targets);
+        }
+        if let Some(boss) = self.boss.as_mut(){
+            boss.shoot(&mut self.bullets);
         }
     }
     
-    pub fn update(&mut self, direction: &(Left, Right)){
+    pub fn update(&mut self, input: &InputFrame){
+        // effects from earlier kills, before new ones are spawned
+        for index in 0..self.effects.len(){
+            self.effects[index].update();
+        }
         // update enemy bullet spawn speed
-        // update player
-        self.player.update(direction, &self.screen);
+        // update players
+        let refill = self.progress.level().ammo_refill;
+        for index in 0..self.players.len(){
+            self.players[index].update(&input.player(index as u8), &self.screen);
+            self.players[index].update_ammo(refill);
+        }
         
-        // update enemies
+        // update enemies, divers head for the nearest player
+        let targets = self.targets();
         for index in 0..self.enemies.len(){
-            self.enemies[index].update();
+            self.enemies[index].update(&self.screen, &targets);
+        }
+        if let Some(boss) = self.boss.as_mut(){
+            boss.update(&self.screen);
+        }
+
+        // power-ups are picked up by flying into them
+        let mut bomber = None;
+        for index in 0..self.power_ups.len(){
+            let power_up = &mut self.power_ups[index];
+            power_up.update(&self.screen);
+            let (x1, y1) = power_up.get_pos();
+            let (x2, y2) = power_up.get_corner_pos();
+            for (p, player) in self.players.iter_mut().enumerate(){
+                let (x3, y3) = player.get_pos();
+                let (x4, y4) = player.get_corner_pos();
+                if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && power_up.is_active() && player.is_active(){
+                    power_up.active = false;
+                    match power_up.kind{
+                        PowerUpKind::Bomb => bomber = Some(p as u8),
+                        kind => player.power_up(kind),
+                    }
+                }
+            }
+        }
+        if let Some(p) = bomber{
+            self.bomb(p);
         }
         
+        // homing missiles go for the middle of enemies and boss
+        let mut aims:Vec<(i16, i16), U11> = Vec::new();
+        for enemy in self.enemies.iter().filter(|enemy| enemy.is_active()){
+            let (x1, y1) = enemy.get_pos();
+            let (x2, y2) = enemy.get_corner_pos();
+            aims.push(((x1 + x2)/2, (y1 + y2)/2)).ok();
+        }
+        if let Some(boss) = self.boss.as_ref(){
+            let (x1, y1) = boss.get_pos();
+            let (x2, y2) = boss.get_corner_pos();
+            aims.push(((x1 + x2)/2, (y1 + y2)/2)).ok();
+        }
+
         // update bullets position
-        for index in 0..self.player.bullets.len(){
-            self.player.bullets[index].update(&self.screen);
+        for p in 0..self.players.len(){
+            let player = &mut self.players[p];
+            for index in 0..player.bullets.len(){
+                player.bullets[index].home_in(&aims);
+                player.bullets[index].update(&self.screen);
 
-            // check if it is hitting anyone.
-            let (x1, y1) = self.player.bullets[index].get_pos();
-            let (x2, y2) = self.player.bullets[index].get_corner_pos();
-            let mut killed = false;
-            for i in 0..self.enemies.len(){
-                let (x3, y3) = self.enemies[i].get_pos();
-                let (x4, y4) = self.enemies[i].get_corner_pos();
-                // here we are making some assumption
-                // *) x1 is always less than x2 because & x3 is always < x4 
-                //      because x1 & x3 are used as origin points for objects and we are not
-                //      drawing outside of display
-                // *) same for y
-                // check if object is active because objects that are killed wont go away until
-                // next frame
-                if !(x3>x2 || x1 > x4 ||  y1 > y4 || y3 > y2) && self.enemies[i].is_active(){
-                    // now these are overlapping
-                    self.player.bullets[index].active = false;
-                    self.enemies[i].active = false;
-                    killed = true;
-                    break;
+                // check if it is hitting anyone.
+                let (x1, y1) = player.bullets[index].get_pos();
+                let (x2, y2) = player.bullets[index].get_corner_pos();
+                let mut killed = false;
+                // overlapping a target, whether it was hit now or gone through before
+                let mut touching = false;
+                // boss soaks up bullets before anyone behind it
+                if let Some(boss) = self.boss.as_mut(){
+                    let (x3, y3) = boss.get_pos();
+                    let (x4, y4) = boss.get_corner_pos();
+                    if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && boss.is_active() && player.bullets[index].is_active(){
+                        // nothing goes through the boss
+                        player.bullets[index].active = false;
+                        boss.damage(player.bullets[index].damage());
+                        boss.killer = p as u8;
+                        if !boss.is_active(){
+                            Self::explode(&mut self.effects, (x3 + x4)/2, (y3 + y4)/2);
+                            self.drops.push(((x3 + x4)/2, (y3 + y4)/2)).ok();
+                        }
+                        killed = true;
+                    }
                 }
-            }
-            // if none of the enemies are killed by bullet then check for asteroids
-            if !killed{
-                for i in 0..self.asteroids.len(){
-                    let (x3, y3) = self.asteroids[i].get_pos();
-                    let (x4, y4) = self.asteroids[i].get_corner_pos();
-                    if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2)&& self.asteroids[i].is_active(){
+                for i in 0..self.enemies.len(){
+                    if killed{
+                        break;
+                    }
+                    let (x3, y3) = self.enemies[i].get_pos();
+                    let (x4, y4) = self.enemies[i].get_corner_pos();
+                    // here we are making some assumption
+                    // *) x1 is always less than x2 because & x3 is always < x4 
+                    //      because x1 & x3 are used as origin points for objects and we are not
+                    //      drawing outside of display
+                    // *) same for y
+                    // check if object is active because objects that are killed wont go away until
+                    // next frame
+                    if !(x3>x2 || x1 > x4 ||  y1 > y4 || y3 > y2) && self.enemies[i].is_active(){
+                        touching = true;
+                        if !player.bullets[index].can_hit(){
+                            continue;
+                        }
                         // now these are overlapping
-                        self.player.bullets[index].active = false;
-                        self.asteroids[i].active = false;
+                        self.enemies[i].damage(player.bullets[index].damage());
+                        player.bullets[index].hit();
+                        self.enemies[i].killer = p as u8;
+                        if !self.enemies[i].is_active(){
+                            Self::explode(&mut self.effects, (x3 + x4)/2, (y3 + y4)/2);
+                            self.drops.push(((x3 + x4)/2, (y3 + y4)/2)).ok();
+                        }
+                        killed = true;
                         break;
                     }
                 }
+                // if none of the enemies are hit by bullet then check for asteroids
+                if !killed{
+                    for i in 0..self.asteroids.len(){
+                        let (x3, y3) = self.asteroids[i].get_pos();
+                        let (x4, y4) = self.asteroids[i].get_corner_pos();
+                        if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2)&& self.asteroids[i].is_active(){
+                            touching = true;
+                            if !player.bullets[index].can_hit(){
+                                continue;
+                            }
+                            // now these are overlapping
+                            self.asteroids[i].damage(player.bullets[index].damage());
+                            player.bullets[index].hit();
+                            self.asteroids[i].killer = p as u8;
+                            if !self.asteroids[i].is_active(){
+                                Self::explode(&mut self.effects, (x3 + x4)/2, (y3 + y4)/2);
+                                self.drops.push(((x3 + x4)/2, (y3 + y4)/2)).ok();
+                                // bigger ones break up if there is room for both pieces
+                                if let Some((left, right)) = self.asteroids[i].split(&self.screen){
+                                    if self.asteroids.capacity() - self.asteroids.len() >= 2{
+                                        self.asteroids.push(left).ok();
+                                        self.asteroids.push(right).ok();
+                                    } else {
+                                        debug!("asteroids full");
+                                    }
+                                }
+                            }
+                            break;
+                        }
+                    }
+                }
+                if !touching{
+                    player.bullets[index].clear_of_targets();
+                }
             }
         }
         // enemy bullets action
@@ -186,15 +391,18 @@ impl GamePool{
             // check if it is hitting anyone.
             let (x1, y1) = self.bullets[index].get_pos();
             let (x2, y2) = self.bullets[index].get_corner_pos();
-            // if the bullet is from opponent
-            let (x3, y3) = self.player.get_pos();
-            // CHEAT: so that player hit box is reduced
-            let x3 = x3 + 1;
-            let y3 = y3 + 1;
-            let (x4, y4) = self.player.get_corner_pos();
-            let x4 = x4 - 1;
-            if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2){
-                self.status = false;
+            for player in self.players.iter_mut(){
+                // if the bullet is from opponent
+                let (x3, y3) = player.get_pos();
+                // CHEAT: so that player hit box is reduced
+                let x3 = x3 + 1;
+                let y3 = y3 + 1;
+                let (x4, y4) = player.get_corner_pos();
+                let x4 = x4 - 1;
+                if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && self.bullets[index].is_active() && player.can_be_hit(){
+                    self.bullets[index].active = false;
+                    player.hit(self.bullets[index].damage());
+                }
             }
         }
         // update asteroids position
@@ -204,36 +412,145 @@ impl GamePool{
             // check for asteroids hit
             let (x1, y1) = self.asteroids[index].get_pos();
             let (x2, y2) = self.asteroids[index].get_corner_pos();
-            let (x3, y3) = self.player.get_pos();
-            let (x4, y4) = self.player.get_corner_pos();
-            if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2){
-                // TODO: set the player to inactive and latery bury
-                self.status = false;
+            for player in self.players.iter_mut(){
+                let (x3, y3) = player.get_pos();
+                let (x4, y4) = player.get_corner_pos();
+                if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && player.can_be_hit(){
+                    player.hit(CRASH_DAMAGE);
+                }
+            }
+        }
+        // flying into enemies, enemy takes no damage just like asteroids
+        for enemy in self.enemies.iter(){
+            let (x1, y1) = enemy.get_pos();
+            let (x2, y2) = enemy.get_corner_pos();
+            for player in self.players.iter_mut(){
+                let (x3, y3) = player.get_pos();
+                let (x4, y4) = player.get_corner_pos();
+                if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && enemy.is_active() && player.can_be_hit(){
+                    player.hit(CRASH_DAMAGE);
+                }
+            }
+        }
+        // flying into the boss
+        if let Some(boss) = self.boss.as_ref(){
+            let (x1, y1) = boss.get_pos();
+            let (x2, y2) = boss.get_corner_pos();
+            for player in self.players.iter_mut(){
+                let (x3, y3) = player.get_pos();
+                let (x4, y4) = player.get_corner_pos();
+                if !(x3>x2 || x1 > x4 || y1 > y4 || y3 > y2) && boss.is_active() && player.can_be_hit(){
+                    player.hit(CRASH_DAMAGE);
+                }
+            }
+        }
+    }
+
+    /// FORMATION_SIZE enemies flying in from above the screen to a row at y, spread evenly
+    /// across the screen. all of them start the same distance away from their slot, so they
+    /// arrive and sway together
+    fn spawn_formation(&mut self, kind:EnemyKind, y:i16, cool_down:u16, level:u8, rand_val:u32){
+        let spacing = self.screen.width() as i16 / FORMATION_SIZE as i16;
+        let width = ENEMY_SPRITE.width() as i16;
+        let entry_x = if rand_val & 1 == 0 { -FORMATION_ENTRY_X } else { FORMATION_ENTRY_X };
+        debug!("spawn: formation at row {:?}", y);
+        for index in 0..FORMATION_SIZE as i16{
+            let x = index * spacing + (spacing - width)/2 + 1;
+            let enemy = Enemy::new(x, y, &ENEMY_SHEET, cool_down)
+                .of_kind(kind, level)
+                .entering_from(x + entry_x, -(ENEMY_SPRITE.height() as i16));
+            self.enemies.push(enemy).expect("couldn't create enemy");
+        }
+    }
+
+    /// everything on screen is destroyed and credited to player p, boss only takes BOMB_DAMAGE.
+    /// bombed kills don't drop power-ups
+    fn bomb(&mut self, p:u8){
+        debug!("bomb dropped by player {:?}", p);
+        for enemy in self.enemies.iter_mut().filter(|enemy| enemy.is_active()){
+            let (x1, y1) = enemy.get_pos();
+            let (x2, y2) = enemy.get_corner_pos();
+            enemy.active = false;
+            enemy.killer = p;
+            Self::explode(&mut self.effects, (x1 + x2)/2, (y1 + y2)/2);
+        }
+        for asteroid in self.asteroids.iter_mut().filter(|asteroid| asteroid.is_active()){
+            let (x1, y1) = asteroid.get_pos();
+            let (x2, y2) = asteroid.get_corner_pos();
+            asteroid.active = false;
+            asteroid.killer = p;
+            Self::explode(&mut self.effects, (x1 + x2)/2, (y1 + y2)/2);
+        }
+        for bullet in self.bullets.iter_mut(){
+            bullet.active = false;
+        }
+        if let Some(boss) = self.boss.as_mut().filter(|boss| boss.is_active()){
+            boss.damage(BOMB_DAMAGE);
+            boss.killer = p;
+            if !boss.is_active(){
+                let (x1, y1) = boss.get_pos();
+                let (x2, y2) = boss.get_corner_pos();
+                Self::explode(&mut self.effects, (x1 + x2)/2, (y1 + y2)/2);
             }
         }
+    }
 
-        // update frame counter 
-        self.fps_ctr +=1;
+    /// middle of every player enemies can go for
+    fn targets(&self)->Vec<(i16, i16), U2>{
+        let mut targets = Vec::new();
+        for player in self.players.iter().filter(|player| player.can_be_hit()){
+            let (x1, y1) = player.get_pos();
+            let (x2, y2) = player.get_corner_pos();
+            targets.push(((x1 + x2)/2, (y1 + y2)/2)).ok();
+        }
+        targets
+    }
+
+    /// explosion with debris flying away from given point. effects are dropped when there is no
+    /// space left for them
+    fn explode(effects:&mut Vec<Effect, U32>, x:i16, y:i16){
+        effects.push(Effect::explosion(x, y)).ok();
+        for &(vel_x, vel_y) in DEBRIS.iter(){
+            if effects.push(Effect::debris(x, y, vel_x, vel_y)).is_err(){
+                debug!("effects full");
+                break;
+            }
+        }
     }
 
     // collects all the elements that are dead and calls burry on them
     pub fn collect(&mut self){
-        if !self.player.is_active(){
-            todo!()
+        // once the explosion is over, player either comes back or sits out the rest of the game
+        let players = self.players.len() as u8;
+        for index in 0..self.players.len(){
+            let player = &mut self.players[index];
+            if !player.is_active() && !player.is_dying() && player.lives > 0{
+                let (x, y) = Self::player_start(&self.screen, index as u8, players);
+                player.respawn(x, y);
+            }
         }
-        let mut removed = 0;
-        for mut index in 0..self.player.bullets.len(){
-            index -= removed;
-            if !self.player.bullets[index].is_active(){
-                self.player.bullets.swap_remove(index);
-                removed +=1;
+        // game is over when nobody is left
+        if self.status && self.players.iter().all(|player| !player.is_active() && !player.is_dying()){
+            debug!("game over, score: {:?}", self.score());
+            self.status = false;
+        }
+        for player in self.players.iter_mut(){
+            let mut removed = 0;
+            for mut index in 0..player.bullets.len(){
+                index -= removed;
+                if !player.bullets[index].is_active(){
+                    player.bullets.swap_remove(index);
+                    removed +=1;
+                }
             }
         }
-        removed =0;
+        let mut removed =0;
         for mut index in 0..self.enemies.len(){
             index -= removed;
             if !self.enemies[index].is_active(){
-                self.enemies.swap_remove(index).bury(&mut self.player.player_score);
+                let enemy = self.enemies.swap_remove(index);
+                let killer = enemy.killer as usize;
+                enemy.bury(&mut self.players[killer].player_score);
                 removed +=1;
             }
         }
@@ -245,64 +562,180 @@ impl GamePool{
                 removed +=1;
             }
         }
+        if self.boss.as_ref().map_or(false, |boss| !boss.is_active()){
+            if let Some(boss) = self.boss.take(){
+                let killer = boss.killer as usize;
+                boss.bury(&mut self.players[killer].player_score);
+            }
+        }
+        removed =0;
+        for mut index in 0..self.power_ups.len(){
+            index -= removed;
+            if !self.power_ups[index].is_active(){
+                self.power_ups.swap_remove(index);
+                removed +=1;
+            }
+        }
+        removed =0;
+        for mut index in 0..self.effects.len(){
+            index -= removed;
+            if !self.effects[index].is_active(){
+                self.effects.swap_remove(index);
+                removed +=1;
+            }
+        }
         removed =0;
         for mut index in 0..self.asteroids.len(){
             index -= removed;
             if !self.asteroids[index].is_active(){
-                self.asteroids.swap_remove(index).bury(&mut self.player.player_score);
+                let asteroid = self.asteroids.swap_remove(index);
+                let killer = asteroid.killer as usize;
+                asteroid.bury(&mut self.players[killer].player_score);
                 removed +=1;
             }
         }
     }
 
     /// draw all objects in the game
-    pub fn draw(&self, disp:&mut Display){
+    pub fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error>{
         // update player
-        self.player.draw(disp);
-        self.screen.draw(disp);
+        for player in self.players.iter(){
+            player.draw(disp)?;
+        }
+        self.screen.draw(disp)?;
         for index in 0..self.enemies.len(){
-            self.enemies[index].draw(disp);
+            self.enemies[index].draw(disp)?;
         }
         for index in 0..self.bullets.len(){
-            self.bullets[index].draw(disp);
+            self.bullets[index].draw(disp)?;
         }
-        for index in 0..self.player.bullets.len(){
-            self.player.bullets[index].draw(disp);
+        for player in self.players.iter(){
+            for index in 0..player.bullets.len(){
+                player.bullets[index].draw(disp)?;
+            }
         }
         for index in 0..self.asteroids.len(){
-            self.asteroids[index].draw(disp);
+            self.asteroids[index].draw(disp)?;
+        }
+        if let Some(boss) = self.boss.as_ref(){
+            boss.draw(disp)?;
+        }
+        for index in 0..self.power_ups.len(){
+            self.power_ups[index].draw(disp)?;
         }
+        for index in 0..self.effects.len(){
+            self.effects[index].draw(disp)?;
+        }
+        if self.progress.show_banner(){
+            let banner = self.level_text();
+            let x = (self.screen.width() as i32 - banner.len() as i32 * 6) / 2 + 1;
+            Text::new(banner.as_str(), Point::new(x, self.screen.height() as i32 / 2 - 4))
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+        }
+        Ok(())
     }
-    pub fn draw_stats(&self, disp:&mut Display){
-        self.stats.border.draw(disp).unwrap();
-        Image::new( 
-            &self.stats.score,
-            Point::new(3, self.screen.height() as i32 +3) )
-            .draw(disp)
-            .unwrap();
-        Image::new( 
-            &self.stats.ammo,
-            Point::new(42, self.screen.height() as i32 + 5) )
-            .draw(disp)
-            .unwrap();
-
-        // player score
-        let score:String<U6> = String::from(self.player.player_score);
+    // "LEVEL N" shown in the banner and stats bar
+    fn level_text(&self)->String<U12>{
+        let mut text:String<U12> = String::from("LEVEL ");
+        let number:String<U4> = String::from(self.level());
+        text.push_str(&number).ok();
+        text
+    }
+    /// stats bar has a level row and a row for every player
+    pub fn draw_stats<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error>{
+        self.stats.border.draw(disp)?;
         Text::new(
-            score.as_str(),
-            Point::new(22, self.screen.height() as i32 + 4)
+            self.level_text().as_str(),
+            Point::new(3, self.screen.height() as i32 + 3)
             )
             .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
-            .draw(disp).unwrap();
+            .draw(disp)?;
+        for (row, player) in self.players.iter().enumerate(){
+            let top = self.screen.height() as i32 + LEVEL_ROW_HEIGHT as i32 + row as i32 * STATS_ROW_HEIGHT as i32;
+            // running power-up or weapon picked up takes the place of the gun, power-ups blink
+            // when about to run out
+            let icon = match player.active_power_up(){
+                Some((kind, ticks)) if ticks > POWER_UP_WARNING_TICKS || ticks / 4 % 2 == 0 => Some(kind.sprite()),
+                Some(_) => None,
+                None if player.weapon() != Weapon::Single => Some(player.weapon().spec().icon),
+                None => {
+                    Image::new( 
+                        &self.stats.score,
+                        Point::new(3, top +3) )
+                        .draw(disp)?;
+                    None
+                },
+            };
+            if let Some(sprite) = icon{
+                let icon:ImageRaw<BinaryColor> = ImageRaw::new(sprite.data, sprite.width as u32, sprite.height as u32);
+                Image::new(&icon, Point::new(7, top + 3)).draw(disp)?;
+            }
+            Image::new( 
+                &self.stats.lives,
+                Point::new(38, top + 5) )
+                .draw(disp)?;
+            Image::new( 
+                &self.stats.ammo,
+                Point::new(52, top + 6) )
+                .draw(disp)?;
 
-        // player ammo
-        let ammo:String<U6> = String::from((self.player.bullets.capacity() - self.player.bullets.len()) as i16);
-        Text::new(
-            ammo.as_str(),
-            Point::new(56, self.screen.height() as i32 + 4)
-            )
-            .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
-            .draw(disp).unwrap();
+            // player score
+            let score:String<U6> = String::from(player.player_score);
+            Text::new(
+                score.as_str(),
+                Point::new(20, top + 4)
+                )
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+
+            // player lives
+            let lives:String<U6> = String::from(player.lives);
+            Text::new(
+                lives.as_str(),
+                Point::new(44, top + 4)
+                )
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+            // health left of the ship, a dot each under the lives
+            for hit in 0..player.health() as i32{
+                let dot = Point::new(38 + hit * 2, top + 9);
+                Rectangle::new(dot, dot)
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(disp)?;
+            }
+
+            // player ammo, or how far the reload is
+            match player.reloading(){
+                Some((left, total)) => {
+                    let done = (5 * (total - left) / total) as i32;
+                    Rectangle::new(Point::new(56, top + 4), Point::new(61, top + 8))
+                        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                        .draw(disp)?;
+                    if done > 0{
+                        Rectangle::new(Point::new(56, top + 4), Point::new(56 + done, top + 8))
+                            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                            .draw(disp)?;
+                    }
+                },
+                None => {
+                    let ammo:String<U6> = String::from(player.ammo());
+                    Text::new(
+                        ammo.as_str(),
+                        Point::new(56, top + 4)
+                        )
+                        .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                        .draw(disp)?;
+                },
+            }
+            // heat gauge under the ammo, weapons that don't overheat never fill it
+            if player.heat() > 0{
+                let heat = (10 * player.heat() as i32 / HEAT_MAX as i32).max(1);
+                Rectangle::new(Point::new(52, top + 9), Point::new(51 + heat, top + 9))
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(disp)?;
+            }
+        }
 
         let fps:String<U6> = String::from(self.fps);
         Text::new(
@@ -310,48 +743,54 @@ impl GamePool{
             Point::new(1, 1)
             )
             .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
-            .draw(disp).unwrap();
+            .draw(disp)
     }
     
-    pub fn is_ok(&self)->bool{
-        !self.status
+    /// score of every player
+    pub fn scores(&self)->Vec<i16, U2>{
+        self.players.iter().map(|player| player.player_score).collect()
     }
 
-    /// resets the fps ctr and sets fps to fps_ctr
-    pub fn set_fps(&mut self){
-        self.fps = self.fps_ctr;
-        self.fps_ctr =0;
+    pub fn effects(&self)->&[Effect]{
+        &self.effects
+    }
+    pub fn enemies(&self)->&[Enemy]{
+        &self.enemies
     }
-}
 
-#[defmt::timestamp]
-fn timestamp() -> u64 {
-    static COUNT: AtomicUsize = AtomicUsize::new(0);
-    let n = COUNT.load(Ordering::Relaxed);
-    COUNT.store(n + 1, Ordering::Relaxed);
-    n as u64
-}
+    pub fn asteroids(&self)->&[Asteroid]{
+        &self.asteroids
+    }
 
-/// Terminates the application and makes `probe-run` exit with exit-code = 0
-pub fn exit() -> ! {
-    loop {
-        cortex_m::asm::bkpt();
+    pub fn power_ups(&self)->&[PowerUp]{
+        &self.power_ups
     }
-}
 
+    pub fn boss(&self)->Option<&Boss>{
+        self.boss.as_ref()
+    }
 
-pub fn final_screen(score:i16, disp:&mut Display, delay:&mut Delay)->!{
-    let image = ImageRaw::new( GUN.data, GUN.width() as u32, GUN.height() as u32);
-    let gun: Image<ImageRaw<BinaryColor>, BinaryColor> = Image::new(
-        &image,
-        Point::new(3, 118)
-        );
-    let score_txt :String<U6> = String::from(score);
-    let score_disp = Text::new(
-            score_txt.as_str(),
-            Point::new(22, 118)
-            )
-            .into_styled(TextStyle::new(Font6x8, BinaryColor::On)) ;
+    /// level being played, 1 for the first one
+    pub fn level(&self)->u8{
+        self.progress.number()
+    }
+
+    pub fn is_ok(&self)->bool{
+        !self.status
+    }
+
+    /// frame rate shown in the corner, measured by the main loop
+    pub fn set_fps(&mut self, fps:u16){
+        self.fps = fps;
+    }
+}
+
+/// draws the game over screen with the score of every player, their total is shown in the middle.
+/// `blink` switches between the "Game" and "Over" caption
+pub fn draw_final_screen<D: DrawTarget<BinaryColor>>(scores:&[i16], blink:bool, disp:&mut D)->Result<(), D::Error>{
+    let total:i16 = scores.iter().sum();
+    let image:ImageRaw<BinaryColor> = ImageRaw::new( GUN.data, GUN.width() as u32, GUN.height() as u32);
+    let score_txt :String<U6> = String::from(total);
     let score_info = Text::new(
             score_txt.as_str(),
             Point::new(
@@ -370,38 +809,993 @@ pub fn final_screen(score:i16, disp:&mut Display, delay:&mut Delay)->!{
 
     let sub = Text::new("you score", Point::new(7, 80))
         .into_styled(TextStyle::new(Font6x8, BinaryColor::On));
-    loop{
-        disp.clear();
-        border.draw(disp).unwrap();
-        game.draw(disp).unwrap();
-        gun.draw(disp).unwrap();
-        score_disp.draw(disp).unwrap();
-        score_info.draw(disp).unwrap();
-        sub.draw(disp).unwrap();
-        disp.flush().unwrap();
-        delay.delay_ms(700u16);
-
-        disp.clear();
-        border.draw(disp).unwrap();
-        over.draw(disp).unwrap();
-        gun.draw(disp).unwrap();
-        score_disp.draw(disp).unwrap();
-        score_info.draw(disp).unwrap();
-        sub.draw(disp).unwrap();
-        disp.flush().unwrap();
-        delay.delay_ms(700u16);
+
+    border.draw(disp)?;
+    if blink{
+        over.draw(disp)?;
+    } else {
+        game.draw(disp)?;
     }
+    // a row for every player, last one at the bottom
+    for (index, score) in scores.iter().enumerate(){
+        let y = 118 - 10 * (scores.len() - 1 - index) as i32;
+        if scores.len() == 1{
+            Image::new(&image, Point::new(3, y)).draw(disp)?;
+        } else {
+            let label = ["P1", "P2"][index];
+            Text::new(label, Point::new(3, y))
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+        }
+        let score_txt :String<U6> = String::from(*score);
+        Text::new(score_txt.as_str(), Point::new(22, y))
+            .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+            .draw(disp)?;
+    }
+    score_info.draw(disp)?;
+    sub.draw(disp)
 }
 
-pub fn display_pause(disp:&mut Display){
+/// draws the title screen, `blink` hides the "press shoot" hint
+pub fn draw_title<D: DrawTarget<BinaryColor>>(blink:bool, disp:&mut D)->Result<(), D::Error>{
+    let image:ImageRaw<BinaryColor> = ImageRaw::new(PLAYER_1_SPRITE.data, PLAYER_1_SPRITE.width() as u32, PLAYER_1_SPRITE.height() as u32);
+    Rectangle::new(Point::zero(), Point::new(63, 127))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(disp)?;
+    Text::new("Space", Point::new(12, 20))
+        .into_styled(TextStyle::new(Font8x16, BinaryColor::On))
+        .draw(disp)?;
+    Text::new("War", Point::new(20, 40))
+        .into_styled(TextStyle::new(Font8x16, BinaryColor::On))
+        .draw(disp)?;
+    Image::new(&image, Point::new(26, 70))
+        .draw(disp)?;
+    if !blink{
+        Text::new("press", Point::new(17, 96))
+            .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+            .draw(disp)?;
+        Text::new("shoot", Point::new(17, 106))
+            .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+            .draw(disp)?;
+    }
+    Ok(())
+}
+
+pub fn draw_pause<D: DrawTarget<BinaryColor>>(disp:&mut D)->Result<(), D::Error>{
     Text::new("Paused", Point::new(8, 56))
         .into_styled(TextStyle::new(Font8x16, BinaryColor::On))
-        .draw(disp).unwrap();
-    disp.flush().unwrap();
+        .draw(disp)
 }
 
-// #[panic_handler]
-// fn panic_handle(info: &PanicInfo)->!{
-//     defmt::debug!("{:?}", info;
-//     exit();
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixed::Fx,
+        rng::XorShift32,
+    };
+
+    fn new_game()->GamePool{
+        GamePool::new(Size::new(64, 128))
+    }
+
+    fn idle_frame()->InputFrame{
+        InputFrame::default()
+    }
+
+    /// enemy bullet, which is ready to be placed anywhere
+    fn enemy_bullet()->Bullet{
+        let game = new_game();
+        let mut enemy = Enemy::new(10, 10, &ENEMY_SHEET, 0);
+        while enemy.bullet_cool_down > 0{
+            enemy.update(&game.screen, &[]);
+        }
+        let mut bullets = Vec::new();
+        enemy.shoot(&mut bullets, &[]);
+        bullets.pop().unwrap()
+    }
+
+    #[test]
+    fn player_bullet_kills_enemy(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        game.players[0].shoot();
+        // bullet moves up by 3 before the hit check
+        game.players[0].bullets[0].set_pos(22, 30);
+        game.update(&idle_frame());
+        assert!(!game.enemies[0].is_active());
+        assert!(!game.players[0].bullets[0].is_active());
+
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert!(game.players[0].bullets.is_empty());
+        assert_eq!(game.players[0].player_score, 1);
+    }
+
+    #[test]
+    fn kill_leaves_explosion_behind(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 30);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.effects().len(), 1 + DEBRIS.len());
+
+        // effects don't stop bullets
+        let (x, y) = game.effects()[0].get_pos();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(x + 4, y + 12);
+        game.update(&idle_frame());
+        game.collect();
+        assert_eq!(game.players[0].bullets.len(), 1);
+
+        for _ in 0..DEBRIS_FRAMES as usize + EXPLOSION_SHEET.len() * EFFECT_FRAME_TICKS as usize{
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert!(game.effects().is_empty());
+    }
+
+    #[test]
+    fn player_bullet_misses_enemy(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        game.players[0].shoot();
+        // one pixel right of the enemy
+        game.players[0].bullets[0].set_pos(32, 30);
+        game.update(&idle_frame());
+        assert!(game.enemies[0].is_active());
+        assert!(game.players[0].bullets[0].is_active());
+    }
+
+    #[test]
+    fn player_bullet_kills_asteroid(){
+        let mut game = new_game();
+        // random value of 1 gives asteroid without x velocity
+        let asteroid = Asteroid::new(20, 40, AsteroidSize::Medium, 1, LEVELS[0].asteroid_speed);
+        game.asteroids.push(asteroid).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 50);
+        game.update(&idle_frame());
+        assert!(!game.asteroids[0].is_active());
+        assert!(!game.players[0].bullets[0].is_active());
+
+        game.collect();
+        // breaks up into small ones
+        assert_eq!(game.asteroids.len(), 2);
+        assert!(game.asteroids.iter().all(|asteroid| asteroid.size() == AsteroidSize::Small));
+        assert_eq!(game.players[0].player_score, MEDIUM_ASTEROID.score);
+    }
+
+    #[test]
+    fn large_asteroid_splits_into_diverging_fragments(){
+        let mut game = new_game();
+        game.asteroids.push(Asteroid::new(20, 40, AsteroidSize::Large, 1, Fx::ZERO)).unwrap();
+        for _ in 0..LARGE_ASTEROID.health{
+            game.players[0].shoot();
+            game.players[0].bullets[0].set_pos(25, 54);
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert_eq!(game.asteroids.len(), 2);
+        assert!(game.asteroids.iter().all(|asteroid| asteroid.size() == AsteroidSize::Medium));
+        assert_eq!(game.players[0].player_score, LARGE_ASTEROID.score);
+
+        // left to right, collect moves the last one into the place of the one shot down
+        let pieces = |game:&GamePool|{
+            let mut pieces = [game.asteroids[0].get_pos(), game.asteroids[1].get_pos()];
+            pieces.sort();
+            pieces
+        };
+        // side by side around the center at 26, they already flew apart half a pixel each for
+        // the rest of the tick they split in
+        assert_eq!(pieces(&game), [(17, 42), (26, 42)]);
+        for _ in 0..8{
+            game.update(&idle_frame());
+        }
+        let drift = (ASTEROID_SPLIT_DRIFT * 8).to_int();
+        assert_eq!(pieces(&game), [(17 - drift, 42), (26 + drift, 42)]);
+    }
+
+    #[test]
+    fn fragments_next_to_a_wall_start_inside_the_screen(){
+        // large asteroid against either wall of the 62 pixel wide screen, and where its pieces
+        // are after the tick it split in. they are moved in far enough to fly apart, not to
+        // bounce off the wall together
+        for &(x, left, right) in &[(1, 2, 11), (50, 44, 53)]{
+            let mut game = new_game();
+            game.asteroids.push(Asteroid::new(x, 40, AsteroidSize::Large, 1, Fx::ZERO)).unwrap();
+            for _ in 0..LARGE_ASTEROID.health{
+                game.players[0].shoot();
+                game.players[0].bullets[0].set_pos(x + 5, 54);
+                game.update(&idle_frame());
+                game.collect();
+            }
+            let mut pieces = [game.asteroids[0].get_pos(), game.asteroids[1].get_pos()];
+            pieces.sort();
+            assert_eq!(pieces, [(left, 42), (right, 42)]);
+        }
+    }
+
+    #[test]
+    fn no_fragments_without_room(){
+        let mut game = new_game();
+        while game.asteroids.len() < game.asteroids.capacity() - 1{
+            game.asteroids.push(Asteroid::new(2, 2, AsteroidSize::Small, 1, Fx::ZERO)).unwrap();
+        }
+        game.asteroids.push(Asteroid::new(20, 40, AsteroidSize::Medium, 1, Fx::ZERO)).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 50);
+        game.update(&idle_frame());
+        game.collect();
+        assert_eq!(game.asteroids.len(), game.asteroids.capacity() - 1);
+        assert_eq!(game.players[0].player_score, MEDIUM_ASTEROID.score);
+    }
+
+    #[test]
+    fn no_lone_fragment_with_one_free_slot(){
+        let mut game = new_game();
+        while game.asteroids.len() < game.asteroids.capacity() - 2{
+            game.asteroids.push(Asteroid::new(2, 2, AsteroidSize::Small, 1, Fx::ZERO)).unwrap();
+        }
+        game.asteroids.push(Asteroid::new(20, 40, AsteroidSize::Medium, 1, Fx::ZERO)).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 50);
+        game.update(&idle_frame());
+        game.collect();
+        // a single piece wouldn't fly apart from anything, so neither is spawned
+        assert_eq!(game.asteroids.len(), game.asteroids.capacity() - 2);
+        assert!(game.asteroids.iter().all(|asteroid| asteroid.get_pos() == (2, 2)));
+        assert_eq!(game.players[0].player_score, MEDIUM_ASTEROID.score);
+    }
+
+    #[test]
+    fn bullet_hits_only_one_target(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        let asteroid = Asteroid::new(20, 20, AsteroidSize::Medium, 1, LEVELS[0].asteroid_speed);
+        game.asteroids.push(asteroid).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 30);
+        game.update(&idle_frame());
+        assert!(!game.enemies[0].is_active());
+        assert!(game.asteroids[0].is_active());
+    }
+
+    #[test]
+    fn enemy_bullet_uses_reduced_player_hitbox(){
+        let (x, y) = new_game().players[0].get_pos();
+        let (_, y2) = new_game().players[0].get_corner_pos();
+
+        // touches the first column of the player sprite, which is not part of the hitbox
+        let mut game = new_game();
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x - 3, y - 2);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert!(game.players[0].is_active());
+
+        // touches the first row of the player sprite, which is not part of the hitbox
+        let mut game = new_game();
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x + 4, y - 5);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert!(game.players[0].is_active());
+
+        assert_eq!(game.players[0].health(), PLAYER_HEALTH);
+
+        // one pixel further in, the bullet hits
+        let mut game = new_game();
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x - 2, y - 2);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert_eq!(game.players[0].health(), PLAYER_HEALTH - 1);
+
+        let mut game = new_game();
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x + 4, y2 - 5);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert_eq!(game.players[0].health(), PLAYER_HEALTH - 1);
+    }
+
+    #[test]
+    fn player_accelerates_and_slides_to_a_stop(){
+        let mut game = new_game();
+        let (start, _) = game.players[0].get_pos();
+        let right = InputFrame::new(false, true, false, false);
+        let mut moved = [0; 5];
+        for step in moved.iter_mut(){
+            game.update(&right);
+            *step = game.players[0].get_pos().0 - start;
+        }
+        assert_eq!(moved, [0, 1, 3, 5, 7]);
+
+        for _ in 0..10{
+            game.update(&idle_frame());
+        }
+        assert_eq!(game.players[0].get_pos().0 - start, 11);
+    }
+
+    #[test]
+    fn strafing_enemy_turns_at_border(){
+        let game = new_game();
+        let max_x = (game.screen.width() - ENEMY_SPRITE.width()) as i16;
+        let mut enemy = Enemy::new(max_x - 4, 20, &ENEMY_SHEET, 0).with_behaviour(Behaviour::Strafe);
+        let mut xs = [0; 12];
+        for x in xs.iter_mut(){
+            enemy.update(&game.screen, &[]);
+            *x = enemy.get_pos().0;
+        }
+        assert_eq!(xs.iter().max(), Some(&max_x));
+        assert!(xs[11] < max_x);
+        assert!(xs.iter().all(|&x| x >= 1));
+    }
+
+    #[test]
+    fn dive_bomber_dives_at_player_and_comes_back(){
+        let game = new_game();
+        let mut enemy = Enemy::new(5, 20, &ENEMY_SHEET, 0).with_behaviour(Behaviour::DiveBomb);
+        let mut ticks = 0;
+        while !enemy.is_diving(){
+            enemy.update(&game.screen, &[(50, 120)]);
+            ticks += 1;
+            assert!(ticks <= ENEMY_DIVE_WAIT + 2);
+        }
+        for _ in 0..20{
+            enemy.update(&game.screen, &[(50, 120)]);
+        }
+        let (x, y) = enemy.get_pos();
+        assert!(x > 5 && y > 20);
+
+        // flies off the bottom and back home from the top
+        while enemy.is_diving(){
+            enemy.update(&game.screen, &[(50, 120)]);
+        }
+        assert!(enemy.get_pos().1 < 0);
+        for _ in 0..40{
+            enemy.update(&game.screen, &[]);
+        }
+        assert_eq!(enemy.get_pos(), (5, 20));
+    }
+
+    #[test]
+    fn formation_flies_in_to_its_row(){
+        let mut game = new_game();
+        game.spawn_formation(FORMATION, 30, 0, 0, 0);
+        assert_eq!(game.enemies.len(), FORMATION_SIZE);
+        for enemy in game.enemies.iter_mut(){
+            assert!(enemy.get_pos().1 < 0);
+            // no shots from above the screen
+            enemy.bullet_cool_down = 0;
+            let mut bullets = Vec::new();
+            enemy.shoot(&mut bullets, &[]);
+            assert!(bullets.is_empty());
+        }
+        for _ in 0..60{
+            for index in 0..game.enemies.len(){
+                game.enemies[index].update(&game.screen, &[]);
+            }
+        }
+        let mut xs = [0; FORMATION_SIZE];
+        for (x, enemy) in xs.iter_mut().zip(game.enemies.iter()){
+            assert_eq!(enemy.behaviour(), Behaviour::Formation);
+            let (enemy_x, enemy_y) = enemy.get_pos();
+            assert_eq!(enemy_y, 30);
+            *x = enemy_x;
+        }
+        // swaying together keeps the spacing
+        assert_eq!(xs[1] - xs[0], xs[2] - xs[1]);
+    }
+
+    /// enemy ready to fire at (10, 10)
+    fn armed_enemy(fire:FirePattern, level:u8)->Enemy{
+        let mut enemy = Enemy::new(10, 10, &ENEMY_SHEET, 50).with_fire(fire, level);
+        enemy.bullet_cool_down = 0;
+        enemy
+    }
+
+    #[test]
+    fn aimed_shot_heads_for_nearest_player(){
+        let mut bullets = Vec::new();
+        armed_enemy(FirePattern::Aimed, 0).shoot(&mut bullets, &[(60, 60), (0, 100)]);
+        let mut bullet = bullets.pop().unwrap();
+        let (x, y) = bullet.get_pos();
+        for _ in 0..20{
+            bullet.update(&new_game().screen);
+        }
+        let (x2, y2) = bullet.get_pos();
+        // down and to the right, at about 45 degrees
+        assert!(x2 - x > 20 && y2 - y > 20);
+        assert!(((x2 - x) - (y2 - y)).abs() <= 2);
+    }
+
+    #[test]
+    fn bullet_aimed_upwards_leaves_the_screen(){
+        let screen = new_game().screen;
+        let mut bullets = Vec::new();
+        // a player who slipped past the enemy is shot at from below
+        armed_enemy(FirePattern::Aimed, 0).shoot(&mut bullets, &[(14, 0)]);
+        let mut bullet = bullets.pop().unwrap();
+        let (_, y) = bullet.get_pos();
+        for _ in 0..20{
+            bullet.update(&screen);
+        }
+        assert!(!bullet.active);
+        assert!(bullet.get_pos().1 < y);
+        assert!(bullet.get_corner_pos().1 <= 0);
+    }
+
+    #[test]
+    fn fans_get_wider_with_level(){
+        let mut bullets = Vec::new();
+        armed_enemy(FirePattern::Spread, 0).shoot(&mut bullets, &[]);
+        assert_eq!(bullets.len(), ENEMY_SPREAD_SHOTS as usize);
+        bullets.clear();
+        armed_enemy(FirePattern::Spread, 20).shoot(&mut bullets, &[]);
+        assert_eq!(bullets.len(), ENEMY_MAX_SHOTS as usize);
+    }
+
+    #[test]
+    fn burst_fires_in_quick_succession(){
+        let game = new_game();
+        let mut enemy = armed_enemy(FirePattern::Burst, 0);
+        let mut bullets = Vec::new();
+        let mut fired_at = [0; 3];
+        let mut tick = 0;
+        while bullets.len() < fired_at.len(){
+            let before = bullets.len();
+            enemy.shoot(&mut bullets, &[]);
+            if bullets.len() > before{
+                fired_at[before] = tick;
+            }
+            enemy.update(&game.screen, &[]);
+            tick += 1;
+        }
+        assert_eq!(fired_at[1] - fired_at[0], ENEMY_BURST_GAP as u32);
+        assert!(fired_at[2] - fired_at[1] > ENEMY_BURST_GAP as u32);
+    }
+
+    #[test]
+    fn asteroid_kills_player(){
+        let mut game = new_game();
+        let (x, y) = game.players[0].get_pos();
+        let asteroid = Asteroid::new(x, y - 4, AsteroidSize::Medium, 1, LEVELS[0].asteroid_speed);
+        game.asteroids.push(asteroid).unwrap();
+        game.update(&idle_frame());
+        assert!(!game.players[0].is_active());
+    }
+
+    #[test]
+    fn player_respawns_until_out_of_lives(){
+        let mut game = new_game();
+        let start = game.players[0].get_pos();
+        for lives in (0..PLAYER_LIVES).rev(){
+            game.players[0].set_pos(5, start.1);
+            game.players[0].hit(PLAYER_HEALTH);
+            assert_eq!(game.players[0].lives, lives);
+            // explosion stays where the player was hit
+            for _ in 0..DEATH_FRAMES{
+                game.collect();
+                assert!(!game.players[0].is_active());
+                assert!(!game.is_ok());
+                game.update(&idle_frame());
+            }
+            game.collect();
+            if lives > 0{
+                assert!(game.players[0].is_active());
+                assert!(game.players[0].is_invulnerable());
+                assert_eq!(game.players[0].get_pos(), start);
+            }
+        }
+        assert!(!game.players[0].is_active());
+        assert!(game.is_ok());
+    }
+
+    #[test]
+    fn respawned_player_is_invulnerable(){
+        let mut game = new_game();
+        let (x, y) = game.players[0].get_pos();
+        game.players[0].respawn(x, y);
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x + 4, y);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert!(game.players[0].is_active());
+        assert_eq!(game.players[0].lives, PLAYER_LIVES);
+
+        for _ in 0..INVULNERABLE_FRAMES{
+            game.update(&idle_frame());
+        }
+        assert!(!game.players[0].is_invulnerable());
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(x + 4, y);
+        game.bullets.push(bullet).unwrap();
+        game.update(&idle_frame());
+        assert!(game.players[0].is_active());
+        assert_eq!(game.players[0].health(), PLAYER_HEALTH - 1);
+        assert_eq!(game.players[0].lives, PLAYER_LIVES);
+    }
+
+    #[test]
+    fn ship_is_lost_once_health_runs_out(){
+        let mut game = new_game();
+        let (x, y) = game.players[0].get_pos();
+        for health in (0..PLAYER_HEALTH).rev(){
+            // blinks and can't be hit for a moment after every hit
+            while game.players[0].is_invulnerable(){
+                game.update(&idle_frame());
+            }
+            let mut bullet = enemy_bullet();
+            bullet.set_pos(x + 4, y);
+            game.bullets.push(bullet).unwrap();
+            game.update(&idle_frame());
+            game.collect();
+            assert_eq!(game.players[0].health(), health);
+            assert_eq!(game.players[0].is_active(), health > 0);
+        }
+        assert_eq!(game.players[0].lives, PLAYER_LIVES - 1);
+        assert!(game.bullets.is_empty());
+
+        // ship comes back whole
+        while !game.players[0].is_active(){
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert_eq!(game.players[0].health(), PLAYER_HEALTH);
+    }
+
+    #[test]
+    fn armoured_enemy_takes_several_hits(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0).of_kind(TANK, 0)).unwrap();
+        for health in (1..TANK.health).rev(){
+            game.players[0].shoot();
+            game.players[0].bullets[0].set_pos(22, 30);
+            game.update(&idle_frame());
+            game.collect();
+            assert_eq!(game.enemies[0].health(), health);
+            assert!(game.players[0].bullets.is_empty());
+            assert!(game.effects().is_empty());
+        }
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(22, 30);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.enemies.is_empty());
+        // worth as much as the hits it took
+        assert_eq!(game.players[0].player_score, TANK.health as i16);
+    }
+
+    #[test]
+    fn collect_compacts_with_swap_remove(){
+        let mut game = new_game();
+        for index in 0..5{
+            game.enemies.push(Enemy::new(index * 12, 20, &ENEMY_SHEET, 0)).unwrap();
+        }
+        game.enemies[0].active = false;
+        game.enemies[3].active = false;
+        game.enemies[4].active = false;
+        game.collect();
+
+        let mut left:[i16; 2] = [game.enemies[0].get_pos().0, game.enemies[1].get_pos().0];
+        left.sort_unstable();
+        assert_eq!(left, [12, 24]);
+        assert_eq!(game.players[0].player_score, 3);
+
+        // all dead
+        for enemy in game.enemies.iter_mut(){
+            enemy.active = false;
+        }
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.players[0].player_score, 5);
+    }
+
+    #[test]
+    fn collect_drops_dead_bullets_without_score(){
+        let mut game = new_game();
+        for _ in 0..3{
+            game.bullets.push(enemy_bullet()).unwrap();
+        }
+        game.bullets[1].active = false;
+        game.players[0].shoot();
+        game.players[0].bullets[0].active = false;
+        game.collect();
+        assert_eq!(game.bullets.len(), 2);
+        assert!(game.players[0].bullets.is_empty());
+        assert_eq!(game.players[0].player_score, 0);
+    }
+
+    #[test]
+    fn spawns_follow_level_table(){
+        let mut rng = XorShift32::new(7);
+        let mut game = new_game();
+        game.spawn(&mut rng);
+        assert_eq!(game.level(), 1);
+        assert_eq!(game.asteroids.len(), LEVELS[0].asteroids as usize);
+        // nobody shows up during the banner
+        for _ in 1..LEVEL_BANNER_TICKS{
+            game.spawn(&mut rng);
+        }
+        assert!(game.enemies.is_empty());
+        // first enemy comes after its delay, where the level says
+        let first = LEVELS[0].waves[0].enemies[0];
+        for _ in 0..first.delay{
+            game.spawn(&mut rng);
+        }
+        assert!(game.enemies.is_empty());
+        game.spawn(&mut rng);
+        assert_eq!(game.enemies.len(), 1);
+        if let Some(pos) = first.pos{
+            assert_eq!(game.enemies[0].get_pos(), pos);
+        }
+
+        // shooting everything down clears the level
+        let mut ticks = 0;
+        while game.level() == 1{
+            game.enemies.clear();
+            game.spawn(&mut rng);
+            ticks += 1;
+            assert!(ticks < 1000);
+        }
+        assert_eq!(game.level(), 2);
+        // asteroids of the new level come with the next spawn
+        game.spawn(&mut rng);
+        assert_eq!(game.asteroids.len(), LEVELS[1].asteroids as usize);
+    }
+
+    #[test]
+    fn boss_comes_after_last_wave(){
+        let mut rng = XorShift32::new(3);
+        let mut game = new_game();
+        while !LEVELS[game.level() as usize - 1].boss{
+            game.enemies.clear();
+            game.spawn(&mut rng);
+        }
+        while game.boss().is_none(){
+            game.enemies.clear();
+            game.spawn(&mut rng);
+        }
+        // no reinforcements or next level during boss fight
+        let level = game.level();
+        for _ in 0..1000{
+            game.spawn(&mut rng);
+        }
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.level(), level);
+        game.boss = None;
+        game.spawn(&mut rng);
+        assert_eq!(game.level(), level + 1);
+    }
+
+    #[test]
+    fn boss_takes_several_hits(){
+        let mut game = new_game();
+        game.boss = Some(Boss::new(16, 20, &BOSS_SPRITE, 2));
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(25, 40);
+        game.update(&idle_frame());
+        game.collect();
+        assert_eq!(game.boss().map(|boss| boss.health()), Some(1));
+        assert!(game.players[0].bullets.is_empty());
+
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(25, 40);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.boss().is_none());
+        assert_eq!(game.players[0].player_score, BOSS_SCORE);
+    }
+
+    /// power-up of given kind right where the first player is
+    fn power_up_on_player(game:&mut GamePool, kind:PowerUpKind){
+        let (x1, y1) = game.players[0].get_pos();
+        let (x2, y2) = game.players[0].get_corner_pos();
+        game.power_ups.push(PowerUp::new((x1 + x2)/2, (y1 + y2)/2, kind)).unwrap();
+    }
+
+    #[test]
+    fn triple_shot_runs_out(){
+        let mut game = new_game();
+        power_up_on_player(&mut game, PowerUpKind::TripleShot);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.power_ups().is_empty());
+        assert_eq!(game.players[0].active_power_up().map(|(kind, _)| kind), Some(PowerUpKind::TripleShot));
+        let magazine = SINGLE_SHOT.magazine;
+        assert_eq!(game.players[0].ammo(), magazine);
+        game.players[0].shoot();
+        assert_eq!(game.players[0].bullets.len(), 3);
+        assert_eq!(game.players[0].ammo(), magazine - 1);
+
+        for _ in 0..POWER_UP_TICKS{
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert!(game.players[0].active_power_up().is_none());
+        game.players[0].bullets.clear();
+        game.players[0].shoot();
+        assert_eq!(game.players[0].bullets.len(), 1);
+    }
+
+    #[test]
+    fn shield_takes_the_hit(){
+        let mut game = new_game();
+        power_up_on_player(&mut game, PowerUpKind::Shield);
+        game.update(&idle_frame());
+        let asteroid = Asteroid::new(0, 0, AsteroidSize::Medium, 1, LEVELS[0].asteroid_speed);
+        let (x, y) = game.players[0].get_pos();
+        game.asteroids.push(asteroid).unwrap();
+        game.asteroids[0].set_pos(x, y);
+        game.update(&idle_frame());
+        assert!(game.players[0].is_active());
+        assert_eq!(game.players[0].lives, PLAYER_LIVES);
+
+        game.players[0].power_up(PowerUpKind::ExtraLife);
+        assert_eq!(game.players[0].lives, PLAYER_LIVES + 1);
+    }
+
+    #[test]
+    fn bomb_clears_the_screen(){
+        let mut game = new_game();
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        let asteroid = Asteroid::new(30, 40, AsteroidSize::Medium, 1, LEVELS[0].asteroid_speed);
+        game.asteroids.push(asteroid).unwrap();
+        let mut bullet = enemy_bullet();
+        bullet.set_pos(5, 5);
+        game.bullets.push(bullet).unwrap();
+        game.boss = Some(Boss::new(16, 20, &BOSS_SPRITE, BOMB_DAMAGE + 1));
+        power_up_on_player(&mut game, PowerUpKind::Bomb);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert!(game.asteroids.is_empty());
+        assert!(game.bullets.is_empty());
+        assert_eq!(game.boss().map(|boss| boss.health()), Some(1));
+        assert_eq!(game.players[0].player_score, 1 + MEDIUM_ASTEROID.score);
+        // nothing but the power-up was picked up
+        assert_eq!(game.players[0].lives, PLAYER_LIVES);
+    }
+
+    #[test]
+    fn weapon_is_picked_up_and_lost_with_the_ship(){
+        let mut game = new_game();
+        power_up_on_player(&mut game, PowerUpKind::Weapon(Weapon::Spread));
+        game.update(&idle_frame());
+        assert_eq!(game.players[0].weapon(), Weapon::Spread);
+        assert_eq!(game.players[0].ammo(), SPREAD_SHOT.magazine);
+        game.players[0].shoot();
+        assert_eq!(game.players[0].bullets.len(), 3);
+        game.players[0].hit(PLAYER_HEALTH);
+        assert_eq!(game.players[0].weapon(), Weapon::Single);
+    }
+
+    #[test]
+    fn laser_goes_through_enemies(){
+        let mut game = new_game();
+        game.players[0].power_up(PowerUpKind::Weapon(Weapon::Laser));
+        game.players[0].shoot();
+        let (x, _) = game.players[0].bullets[0].get_pos();
+        game.enemies.push(Enemy::new(x - 4, 20, &ENEMY_SHEET, 0)).unwrap();
+        game.enemies.push(Enemy::new(x - 4, 40, &ENEMY_SHEET, 0)).unwrap();
+        while game.enemies[1].is_active(){
+            game.update(&idle_frame());
+        }
+        assert!(game.enemies[0].is_active());
+        assert!(game.players[0].bullets[0].is_active());
+        while game.players[0].bullets[0].get_pos().1 > 10{
+            game.update(&idle_frame());
+        }
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.players[0].bullets.len(), 1);
+        assert_eq!(game.players[0].player_score, 2);
+    }
+
+    #[test]
+    fn piercing_shot_hits_armoured_enemy_once(){
+        let mut game = new_game();
+        game.players[0].power_up(PowerUpKind::Weapon(Weapon::Piercing));
+        game.players[0].shoot();
+        let (x, _) = game.players[0].bullets[0].get_pos();
+        game.enemies.push(Enemy::new(x - 4, 40, &ENEMY_SHEET, 0).of_kind(TANK, 0)).unwrap();
+        // takes several ticks to get through
+        while game.players[0].bullets[0].get_pos().1 > 20{
+            game.update(&idle_frame());
+        }
+        assert!(game.players[0].bullets[0].is_active());
+        assert_eq!(game.enemies[0].health(), TANK.health - PIERCING_SHOT.damage);
+    }
+
+    #[test]
+    fn piercing_shot_goes_through_two_targets(){
+        let mut game = new_game();
+        game.players[0].power_up(PowerUpKind::Weapon(Weapon::Piercing));
+        game.players[0].shoot();
+        let (x, _) = game.players[0].bullets[0].get_pos();
+        for &y in &[70, 50, 30, 10]{
+            game.enemies.push(Enemy::new(x - 4, y, &ENEMY_SHEET, 0)).unwrap();
+        }
+        while game.players[0].bullets[0].is_active(){
+            game.update(&idle_frame());
+        }
+        game.collect();
+        assert_eq!(game.enemies.len(), 1);
+        assert_eq!(game.enemies[0].get_pos().1, 10);
+        assert_eq!(game.players[0].player_score, 3);
+    }
+
+    #[test]
+    fn homing_missile_turns_towards_enemy(){
+        let mut game = new_game();
+        game.players[0].power_up(PowerUpKind::Weapon(Weapon::Homing));
+        game.enemies.push(Enemy::new(40, 10, &ENEMY_SHEET, 0)).unwrap();
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(10, 80);
+        for _ in 0..40{
+            game.update(&idle_frame());
+        }
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.players[0].player_score, 1);
+    }
+
+    #[test]
+    fn boss_takes_weapon_damage(){
+        let mut game = new_game();
+        game.players[0].power_up(PowerUpKind::Weapon(Weapon::Piercing));
+        game.boss = Some(Boss::new(16, 20, &BOSS_SPRITE, 10));
+        game.players[0].shoot();
+        game.players[0].bullets[0].set_pos(25, 40);
+        game.update(&idle_frame());
+        game.collect();
+        assert_eq!(game.boss().map(|boss| boss.health()), Some(10 - PIERCING_SHOT.damage));
+        // piercing shots stop at the boss
+        assert!(game.players[0].bullets.is_empty());
+    }
+
+    #[test]
+    fn empty_magazine_reloads(){
+        let mut game = new_game();
+        let player = &mut game.players[0];
+        for _ in 0..SINGLE_SHOT.magazine{
+            assert!(player.can_shoot());
+            player.shoot();
+            player.bullets.clear();
+        }
+        assert_eq!(player.ammo(), 0);
+        assert!(!player.can_shoot());
+        assert_eq!(player.reloading(), Some((SINGLE_SHOT.reload_ticks, SINGLE_SHOT.reload_ticks)));
+        for _ in 0..SINGLE_SHOT.reload_ticks{
+            assert!(!player.can_shoot());
+            player.update_ammo(0);
+        }
+        assert_eq!(player.reloading(), None);
+        assert_eq!(player.ammo(), SINGLE_SHOT.magazine);
+        assert!(player.can_shoot());
+    }
+
+    #[test]
+    fn rounds_come_back_at_level_rate(){
+        let mut game = new_game();
+        let player = &mut game.players[0];
+        player.shoot();
+        player.shoot();
+        for _ in 0..9{
+            player.update_ammo(10);
+        }
+        assert_eq!(player.ammo(), SINGLE_SHOT.magazine - 2);
+        player.update_ammo(10);
+        assert_eq!(player.ammo(), SINGLE_SHOT.magazine - 1);
+        for _ in 0..100{
+            player.update_ammo(10);
+        }
+        assert_eq!(player.ammo(), SINGLE_SHOT.magazine);
+        // no refill at all
+        player.shoot();
+        for _ in 0..100{
+            player.update_ammo(0);
+        }
+        assert_eq!(player.ammo(), SINGLE_SHOT.magazine - 1);
+    }
+
+    #[test]
+    fn laser_overheats_and_cools_down(){
+        let mut game = new_game();
+        let player = &mut game.players[0];
+        player.power_up(PowerUpKind::Weapon(Weapon::Laser));
+        let mut shots = 0;
+        while player.can_shoot(){
+            player.shoot();
+            player.bullets.clear();
+            shots += 1;
+        }
+        assert_eq!(shots, (HEAT_MAX + LASER_BEAM.heat - 1) / LASER_BEAM.heat);
+        assert!(player.is_overheated());
+        assert!(player.ammo() > 0);
+        for _ in 0..HEAT_MAX / HEAT_COOLING - 1{
+            player.update_ammo(0);
+            assert!(!player.can_shoot());
+        }
+        player.update_ammo(0);
+        assert_eq!(player.heat(), 0);
+        assert!(player.can_shoot());
+    }
+
+    #[test]
+    fn every_power_up_can_drop(){
+        let total:u32 = POWER_UP_DROPS.iter().map(|&(_, weight)| weight as u32).sum();
+        for &(kind, weight) in POWER_UP_DROPS.iter(){
+            let count = (0..total).filter(|&roll| PowerUpKind::pick(roll) == kind).count();
+            assert_eq!(count, weight as usize);
+        }
+    }
+
+    #[test]
+    fn kill_is_credited_to_shooting_player(){
+        let mut game = GamePool::with_players(Size::new(64, 128), 2);
+        game.enemies.push(Enemy::new(20, 20, &ENEMY_SHEET, 0)).unwrap();
+        game.players[1].shoot();
+        game.players[1].bullets[0].set_pos(22, 30);
+        game.update(&idle_frame());
+        game.collect();
+        assert!(game.enemies.is_empty());
+        assert_eq!(game.players[0].player_score, 0);
+        assert_eq!(game.players[1].player_score, 1);
+        assert_eq!(game.score(), 1);
+    }
+
+    #[test]
+    fn game_is_over_when_both_players_are_out(){
+        let mut game = GamePool::with_players(Size::new(64, 128), 2);
+        assert!(game.players[0].get_pos().0 < game.players[1].get_pos().0);
+        game.players[0].lives = 1;
+        game.players[0].hit(PLAYER_HEALTH);
+        for _ in 0..=DEATH_FRAMES{
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert!(!game.players[0].is_active());
+        assert!(game.players[1].is_active());
+        assert!(!game.is_ok());
+
+        game.players[1].lives = 1;
+        game.players[1].hit(PLAYER_HEALTH);
+        for _ in 0..=DEATH_FRAMES{
+            game.update(&idle_frame());
+            game.collect();
+        }
+        assert!(game.is_ok());
+    }
+
+    #[test]
+    fn spawn_positions_are_seeded(){
+        let play = |seed|{
+            let mut game = new_game();
+            let mut rng = XorShift32::new(seed);
+            for _ in 0..LEVEL_BANNER_TICKS + 100{
+                game.spawn(&mut rng);
+            }
+            game
+        };
+        let (game, again) = (play(42), play(42));
+        assert!(!game.asteroids().is_empty());
+        assert!(!game.enemies().is_empty());
+        assert!(game.asteroids().iter().zip(again.asteroids()).all(|(a, b)| a.get_pos() == b.get_pos() && a.size() == b.size()));
+        assert!(game.enemies().iter().zip(again.enemies()).all(|(a, b)| a.get_pos() == b.get_pos()));
+        // asteroids come in from above the screen
+        assert!(game.asteroids().iter().all(|asteroid| asteroid.get_pos().1 == 1 - asteroid.size().spec().sheet.height() as i16));
+
+        // seed 42 always puts the first asteroid at the same place
+        let mut game = new_game();
+        game.spawn(&mut XorShift32::new(42));
+        assert_eq!(game.asteroids().len(), 1);
+        assert_eq!(game.asteroids()[0].size(), AsteroidSize::Large);
+        assert_eq!(game.asteroids()[0].get_pos(), (32, -11));
+    }
+}
@@ -0,0 +1,12 @@
+// limits of the game that build.rs checks assets/levels.toml against. the file is included by
+// both build.rs and `objects`, so it can only hold plain constants
+
+// asteroids on screen at once, the capacity of `GamePool::asteroids`
+pub const MAX_ASTEROIDS: usize = 20;
+// enemies on screen at once, the capacity of `GamePool::enemies`
+pub const MAX_WAVE_ENEMIES: usize = 10;
+// enemies flying in formation together
+pub const FORMATION_SIZE: usize = 3;
+// enemies stay in the upper part of the screen of a 64x128 display
+pub const MAX_ENEMY_X: i16 = 51;
+pub const MAX_ENEMY_Y: i16 = 60;
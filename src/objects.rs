@@ -1,15 +1,20 @@
-use crate::game::Sprite;
-// these are raw bytes of sprites
-pub static PLAYER_1_SPRITE : Sprite = Sprite{
-    data    :   &[0x04, 0x00, 0x0e, 0x00, 0x0e, 0x00, 0x5f, 0x40, 0xdf, 0x60, 0xff, 0xe0, 0xdf, 0x60, 0x5b, 0x40, 0x11, 0x00],
-    width   :   11,
-    height  :   9,
-};
-pub static PLAYER_2_SPRITE : Sprite = Sprite{
-    data    :   &[0x04, 0x00, 0x04, 0x00, 0x0e, 0x00, 0x1f, 0x00, 0x3f, 0x80, 0x7f, 0xc0, 0xff, 0xe0, 0xff, 0xe0, 0x7f, 0xc0, 0x2e, 0x80],
-    width   :   11,
-    height  :   10,
+use crate::{
+    game::{AsteroidSize, AsteroidSpec, Behaviour, EnemyKind, FirePattern, PowerUpKind, Sprite, Weapon, WeaponSpec},
+    animation::SpriteSheet,
+    fixed::Fx,
+    level::{Level, Wave, WaveEnemy},
 };
+// sprites drawn in assets/, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/sprites.rs"));
+// LEVELS, from assets/levels.toml, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/levels.rs"));
+// MAX_ASTEROIDS, FORMATION_SIZE and the other limits build.rs checks the levels against
+include!("limits.rs");
+
+// sprite of every player, indexed by player
+pub static PLAYER_SPRITES : [&Sprite; 2] = [&PLAYER_1_SPRITE, &PLAYER_2_SPRITE];
+
+// these are raw bytes of sprites that have no art in assets/
 
 // pub static BULLET_SPRITE : Sprite = Sprite{
 //     data    : &[0x70, 0xf8, 0xf8, 0xf8, 0x70],
@@ -17,37 +22,27 @@ pub static PLAYER_2_SPRITE : Sprite = Sprite{
 //     height  :5
 // };
 
-pub static BULLET_SPRITE : Sprite = Sprite{
-    data    : &[0x40, 0xe0, 0xe0],
-    width   : 3,
-    height  :3
+pub static EXPLOSION_SPRITE_1: Sprite = Sprite{
+    data    : &[0x04, 0x00, 0x44, 0x40, 0x20, 0x80, 0x0e, 0x00, 0xdb, 0x60, 0x0e, 0x00, 0x20, 0x80, 0x44, 0x40, 0x04, 0x00],
+    width   : 11,
+    height  : 9,
 };
-
-pub static ENEMY_SPRITE :Sprite = Sprite{
-    data    : &[0x0e, 0x00, 0x1f, 0x00, 0x3b, 0x80, 0x31, 0x80, 0x35, 0x80, 0x75, 0xc0, 0xff, 0xe0, 0xff, 0xe0, 0x7f, 0xc0, 0x31, 0x80],
+pub static EXPLOSION_SPRITE_2: Sprite = Sprite{
+    data    : &[0x80, 0x20, 0x11, 0x00, 0x40, 0x40, 0x04, 0x00, 0x2a, 0x80, 0x04, 0x00, 0x40, 0x40, 0x11, 0x00, 0x80, 0x20],
     width   : 11,
-    height  : 10,
+    height  : 9,
 };
 
-pub static ASTEROID_SPRITE_1: Sprite = Sprite{
-    data    : &[0xe3, 0xa1, 0x3f, 0x3c, 0x3c, 0xfc, 0x85, 0xc7, ],
-    width   : 8,
-    height  : 8,
-};
-pub static ASTEROID_SPRITE_2: Sprite = Sprite{
-    data    : &[0xf1, 0x11, 0x3d, 0x3f, 0xfc, 0xbc, 0x88, 0x8f, ],
-    width   : 8,
-    height  : 8,
-};
-pub static ASTEROID_SPRITE_3: Sprite = Sprite{
-    data    : &[0x78, 0x09, 0x3d, 0xfd, 0xbf, 0xbc, 0x90, 0x1e, ],
-    width   : 8,
-    height  : 8,
+pub static EXPLOSION_SPRITE_3: Sprite = Sprite{
+    data    : &[0x00, 0x00, 0x20, 0x80, 0x00, 0x00, 0x00, 0x00, 0x80, 0x20, 0x00, 0x00, 0x00, 0x00, 0x20, 0x80, 0x00, 0x00],
+    width   : 11,
+    height  : 9,
 };
-pub static ASTEROID_SPRITE_4: Sprite = Sprite{
-    data    : &[0x3c, 0x04, 0xfd, 0xbd, 0xbd, 0xbf, 0x20, 0x3c  ],
-    width   : 8,
-    height  : 8,
+
+pub static HEART: Sprite = Sprite{
+    data    : &[0x50, 0xf8, 0xf8, 0x70, 0x20],
+    width   : 5,
+    height  : 5,
 };
 
 pub static GUN: Sprite = Sprite{
@@ -56,12 +51,188 @@ pub static GUN: Sprite = Sprite{
     height  : 8,
 };
 
-pub static AMMO: Sprite = Sprite{
-    data    : &[0xfe, 0x00, 0xff, 0x80, 0xff, 0xc0, 0xff, 0x80, 0xfe, 0x00],
-    width   : 10,
-    height  : 5,
+// weapons of the player, speeds are pixels a tick upwards
+pub static SINGLE_SHOT: WeaponSpec = WeaponSpec{
+    sprite: &BULLET_SPRITE, icon: &GUN, speed: Fx::from_int(-3), spread: Fx::ZERO,
+    shots: 1, damage: 1, pierce: 0, homing: false, in_air: 3,
+    magazine: 8, reload_ticks: 45, heat: 0,
+};
+pub static SPREAD_SHOT: WeaponSpec = WeaponSpec{
+    sprite: &BULLET_SPRITE, icon: &SPREAD_ICON, speed: Fx::from_int(-3), spread: Fx::from_raw(16),
+    shots: 3, damage: 1, pierce: 0, homing: false, in_air: 2,
+    magazine: 5, reload_ticks: 60, heat: 0,
+};
+// goes through everything but the boss, overheats when fired continuously
+pub static LASER_BEAM: WeaponSpec = WeaponSpec{
+    sprite: &LASER_SPRITE, icon: &LASER_ICON, speed: Fx::from_int(-6), spread: Fx::ZERO,
+    shots: 1, damage: 1, pierce: u8::MAX, homing: false, in_air: 1,
+    magazine: 9, reload_ticks: 60, heat: 30,
+};
+pub static PIERCING_SHOT: WeaponSpec = WeaponSpec{
+    sprite: &PIERCING_SPRITE, icon: &PIERCING_ICON, speed: Fx::from_int(-4), spread: Fx::ZERO,
+    shots: 1, damage: 2, pierce: 2, homing: false, in_air: 2,
+    magazine: 6, reload_ticks: 50, heat: 0,
+};
+pub static HOMING_MISSILE: WeaponSpec = WeaponSpec{
+    sprite: &MISSILE_SPRITE, icon: &HOMING_ICON, speed: Fx::from_int(-2), spread: Fx::ZERO,
+    shots: 1, damage: 3, pierce: 0, homing: true, in_air: 2,
+    magazine: 4, reload_ticks: 75, heat: 0,
+};
+
+// animations
+pub static ASTEROID_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&ASTEROID_SPRITE_1, &ASTEROID_SPRITE_2, &ASTEROID_SPRITE_3, &ASTEROID_SPRITE_4],
+};
+pub static LARGE_ASTEROID_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&LARGE_ASTEROID_SPRITE_1, &LARGE_ASTEROID_SPRITE_2],
+};
+pub static SMALL_ASTEROID_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&SMALL_ASTEROID_SPRITE_1, &SMALL_ASTEROID_SPRITE_2],
+};
+
+// asteroid classes, like enemies the tougher ones are worth more
+pub static LARGE_ASTEROID: AsteroidSpec = AsteroidSpec{
+    sheet: &LARGE_ASTEROID_SHEET, health: 2, score: 3, fragment: Some(AsteroidSize::Medium),
+};
+pub static MEDIUM_ASTEROID: AsteroidSpec = AsteroidSpec{
+    sheet: &ASTEROID_SHEET, health: 1, score: 2, fragment: Some(AsteroidSize::Small),
+};
+pub static SMALL_ASTEROID: AsteroidSpec = AsteroidSpec{
+    sheet: &SMALL_ASTEROID_SHEET, health: 1, score: 1, fragment: None,
+};
+// enemy blinking and shuffling its feet
+pub static ENEMY_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&ENEMY_SPRITE, &ENEMY_SPRITE_2],
+};
+// flame under the player's ship
+pub static ENGINE_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&ENGINE_SPRITE_1, &ENGINE_SPRITE_2],
+};
+pub static EXPLOSION_SHEET: SpriteSheet = SpriteSheet{
+    frames  : &[&EXPLOSION_SPRITE_1, &EXPLOSION_SPRITE_2, &EXPLOSION_SPRITE_3],
 };
 
 // Constants
-pub const FPS_LIMIT:u16         = 10;
-pub const LEVEL_SCORE:u8        = 40;
+// game logic runs at fixed TICK_RATE ticks a second, frames are drawn at most FPS_LIMIT times
+pub const TICK_RATE:u32         = 30;
+pub const FPS_LIMIT:u16         = 30;
+// most ticks run back to back to catch up, time beyond that is dropped
+pub const MAX_CATCH_UP:u32      = 5;
+// frames the profiler sums up before reporting
+pub const PROFILE_FRAMES:u32    = 60;
+// frames between blinks of title and game over screen
+pub const BLINK_FRAMES:u32      = 7;
+// frames game over screen is shown before shoot returns to title
+pub const GAME_OVER_FRAMES:u32  = 20;
+pub const PLAYER_LIVES:u8       = 3;
+// hits a ship takes before a life is lost, crashing into anything loses it right away
+pub const PLAYER_HEALTH:u8      = 3;
+pub const CRASH_DAMAGE:u8       = PLAYER_HEALTH;
+// ticks player can't be hit again after a hit it survived
+pub const HIT_INVULNERABLE_TICKS:u16 = 15;
+// ticks enemies, asteroids and boss blink after a hit they survived
+pub const HIT_FLASH_TICKS:u8    = 6;
+pub const MAX_LIVES:u8          = 9;
+// speeds in pixels a frame, fractions are sixteenths of a pixel
+pub const PLAYER_ACCEL:Fx       = Fx::from_raw(8);
+pub const PLAYER_FRICTION:Fx    = Fx::from_raw(6);
+pub const PLAYER_MAX_SPEED:Fx   = Fx::from_int(2);
+pub const ENEMY_BULLET_SPEED:Fx = Fx::from_int(2);
+pub const ASTEROID_DRIFT:Fx     = Fx::from_raw(8);
+// sideways speed fragments of a shot down asteroid fly apart with
+pub const ASTEROID_SPLIT_DRIFT:Fx = Fx::from_raw(8);
+// sizes of asteroids flying in, picked at random
+pub const ASTEROID_SIZES:[AsteroidSize; 3] = [AsteroidSize::Large, AsteroidSize::Medium, AsteroidSize::Medium];
+pub const ENEMY_STRAFE_SPEED:Fx = Fx::from_raw(12);
+// speed enemies fly in and come back after a dive at
+pub const ENEMY_ENTRY_SPEED:Fx  = Fx::ONE;
+pub const ENEMY_DIVE_SPEED:Fx   = Fx::from_raw(40);
+// how fast a diving enemy turns towards the player
+pub const ENEMY_DIVE_STEER:Fx   = Fx::from_raw(3);
+// ticks a dive bomber waits before diving
+pub const ENEMY_DIVE_WAIT:u16   = 60;
+// sideways offset of swaying enemies in pixels, a step every ENEMY_SWAY_TICKS
+pub const ENEMY_SWAY:[i8; 16]   = [0, 3, 6, 7, 8, 7, 6, 3, 0, -3, -6, -7, -8, -7, -6, -3];
+pub const ENEMY_SWAY_TICKS:u16  = 3;
+// kinds of enemies waves are made of, score is their health
+pub const HOVERER:EnemyKind     = EnemyKind{ behaviour: Behaviour::Hover,     fire: FirePattern::Straight,    health: 1 };
+pub const STRAFER:EnemyKind     = EnemyKind{ behaviour: Behaviour::Strafe,    fire: FirePattern::Aimed,       health: 1 };
+pub const SWAYER:EnemyKind      = EnemyKind{ behaviour: Behaviour::SineWave,  fire: FirePattern::Spread,      health: 1 };
+pub const DIVER:EnemyKind       = EnemyKind{ behaviour: Behaviour::DiveBomb,  fire: FirePattern::Burst,       health: 1 };
+pub const FORMATION:EnemyKind   = EnemyKind{ behaviour: Behaviour::Formation, fire: FirePattern::Alternating, health: 1 };
+// armoured ones
+pub const TANK:EnemyKind        = EnemyKind{ behaviour: Behaviour::Hover,     fire: FirePattern::Spread,      health: 4 };
+pub const GUNSHIP:EnemyKind     = EnemyKind{ behaviour: Behaviour::Strafe,    fire: FirePattern::Burst,       health: 3 };
+// sideways speed between neighbouring bullets of a fan
+pub const ENEMY_SPREAD:Fx       = Fx::from_raw(10);
+pub const ENEMY_SPREAD_SHOTS:u8 = 3;
+pub const ENEMY_BURST_SHOTS:u8  = 2;
+// fans and bursts get a bullet more every ENEMY_SHOT_LEVELS levels, up to ENEMY_MAX_SHOTS
+pub const ENEMY_SHOT_LEVELS:u8  = 2;
+pub const ENEMY_MAX_SHOTS:u8    = 5;
+// ticks between bullets of a burst
+pub const ENEMY_BURST_GAP:u16   = 6;
+// how far to the side of its slot an enemy of a formation starts flying in
+pub const FORMATION_ENTRY_X:i16 = 16;
+// players sharing the screen
+pub const MAX_PLAYERS:u8        = 2;
+// height of the level row on top of the stats bar
+pub const LEVEL_ROW_HEIGHT:u8   = 9;
+// height of a player's row in the stats bar
+pub const STATS_ROW_HEIGHT:u8   = 10;
+// ticks the "Level N" banner is shown before a level starts
+pub const LEVEL_BANNER_TICKS:u16 = 60;
+// frames the explosion is shown after player is hit
+pub const DEATH_FRAMES:u8       = 12;
+// ticks each frame of an explosion is shown, whole explosion takes DEATH_FRAMES
+pub const EXPLOSION_FRAME_TICKS:u8 = 4;
+pub const ASTEROID_FRAME_TICKS:u8 = 2;
+pub const ENEMY_FRAME_TICKS:u8  = 8;
+// ticks each frame of explosion effect on kills is shown
+pub const EFFECT_FRAME_TICKS:u8 = 2;
+// frames debris flies after a kill
+pub const DEBRIS_FRAMES:u8      = 8;
+// direction of every debris pixel of a kill
+pub const DEBRIS:[(Fx, Fx); 6]  = [
+    (Fx::from_int(-1), Fx::from_int(-1)), (Fx::from_int(1), Fx::from_int(-1)),
+    (Fx::from_int(-2), Fx::ZERO),         (Fx::from_int(2), Fx::ZERO),
+    (Fx::from_int(-1), Fx::from_int(1)),  (Fx::from_int(1), Fx::from_int(1)),
+];
+// frames player can't be hit after respawn
+pub const INVULNERABLE_FRAMES:u16 = 40;
+pub const BOSS_HEALTH:u8        = 20;
+// extra health for every boss fought before
+pub const BOSS_HEALTH_PER_LEVEL:u8 = 4;
+pub const BOSS_SCORE:i16        = 25;
+// row boss stops at after flying in
+pub const BOSS_TOP:i16          = 12;
+// what kills drop, with weights. how often they drop is up to the level
+pub const POWER_UP_DROPS:[(PowerUpKind, u8); 9] = [
+    (PowerUpKind::RapidFire, 25),
+    (PowerUpKind::TripleShot, 20),
+    (PowerUpKind::Shield, 20),
+    (PowerUpKind::ExtraLife, 10),
+    (PowerUpKind::Bomb, 10),
+    (PowerUpKind::Weapon(Weapon::Spread), 5),
+    (PowerUpKind::Weapon(Weapon::Laser), 4),
+    (PowerUpKind::Weapon(Weapon::Piercing), 4),
+    (PowerUpKind::Weapon(Weapon::Homing), 4),
+];
+pub const POWER_UP_SPEED:Fx     = Fx::from_raw(12);
+// ticks rapid fire and triple shot last
+pub const POWER_UP_TICKS:u16    = 300;
+pub const SHIELD_TICKS:u16      = 200;
+// sideways speed of the outer bullets of a triple shot
+pub const TRIPLE_SHOT_SPREAD:Fx = Fx::from_raw(8);
+// fastest a homing missile flies sideways, and how fast it gets there
+pub const HOMING_TURN_SPEED:Fx  = Fx::from_raw(24);
+pub const HOMING_STEER:Fx       = Fx::from_raw(4);
+// weapons overheat once the gauge is full and can't fire till it has cooled down all the way
+pub const HEAT_MAX:u8           = 100;
+// heat lost every tick
+pub const HEAT_COOLING:u8       = 1;
+// damage a bomb does to the boss
+pub const BOMB_DAMAGE:u8        = 5;
+// power-up indicator and shield blink when this close to running out
+pub const POWER_UP_WARNING_TICKS:u16 = 60;
+
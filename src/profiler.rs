@@ -0,0 +1,233 @@
+// Frame profiler: time spent in every phase of a frame, summarised as min/avg/max over a window
+// of PROFILE_FRAMES frames.
+//
+// time is counted by a `CycleCounter`, the DWT cycle counter on the board and a nanosecond
+// clock on the host, so numbers are only comparable on the same target.
+use crate::objects::PROFILE_FRAMES;
+
+use embedded_graphics::{
+    prelude::*,
+    fonts::{Font6x8, Text},
+    pixelcolor::BinaryColor,
+    style::TextStyle,
+};
+
+use heapless::{
+    String,
+    consts::*,
+};
+
+/// Free running counter, allowed to wrap around.
+pub trait CycleCounter{
+    fn cycles(&mut self)->u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase{
+    Spawn,
+    Update,
+    Collect,
+    Draw,
+    Flush,
+}
+
+impl Phase{
+    pub const ALL: [Phase; 5] = [Phase::Spawn, Phase::Update, Phase::Collect, Phase::Draw, Phase::Flush];
+
+    pub fn name(self)->&'static str{
+        match self{
+            Phase::Spawn => "spawn",
+            Phase::Update => "update",
+            Phase::Collect => "collect",
+            Phase::Draw => "draw",
+            Phase::Flush => "flush",
+        }
+    }
+}
+
+/// time spent in a phase over the last window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary{
+    pub min: u32,
+    pub avg: u32,
+    pub max: u32,
+    /// number of times the phase ran
+    pub samples: u32,
+}
+
+/// Runs a phase of the frame, measuring it or not.
+pub trait Probe{
+    fn measure<T, F: FnOnce()->T>(&mut self, phase:Phase, f:F)->T;
+}
+
+/// no profiling
+impl Probe for (){
+    fn measure<T, F: FnOnce()->T>(&mut self, _:Phase, f:F)->T{
+        f()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window{
+    min: u32,
+    max: u32,
+    total: u64,
+    samples: u32,
+}
+
+impl Window{
+    const EMPTY: Self = Self{ min: u32::MAX, max: 0, total: 0, samples: 0 };
+
+    fn add(&mut self, cycles:u32){
+        self.min = self.min.min(cycles);
+        self.max = self.max.max(cycles);
+        self.total += cycles as u64;
+        self.samples += 1;
+    }
+    fn summary(&self)->Summary{
+        if self.samples == 0{
+            return Summary::default();
+        }
+        Summary{
+            min: self.min,
+            avg: (self.total / self.samples as u64) as u32,
+            max: self.max,
+            samples: self.samples,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Profiler<C>{
+    counter: C,
+    current: [Window; 5],
+    last: [Summary; 5],
+    frames: u32,
+    overlay: bool,
+}
+
+impl<C: CycleCounter> Profiler<C>{
+    pub fn new(counter:C)->Self{
+        Self{
+            counter,
+            current: [Window::EMPTY; 5],
+            last: [Summary::default(); 5],
+            frames: 0,
+            overlay: false,
+        }
+    }
+    pub fn record(&mut self, phase:Phase, cycles:u32){
+        self.current[phase as usize].add(cycles);
+    }
+    /// to be called after every drawn frame, returns true when a window is complete and
+    /// `summary` has new numbers
+    pub fn end_frame(&mut self)->bool{
+        self.frames += 1;
+        if self.frames < PROFILE_FRAMES{
+            return false;
+        }
+        for phase in Phase::ALL.iter(){
+            self.last[*phase as usize] = self.current[*phase as usize].summary();
+        }
+        self.current = [Window::EMPTY; 5];
+        self.frames = 0;
+        true
+    }
+    /// numbers of the last complete window
+    pub fn summary(&self, phase:Phase)->Summary{
+        self.last[phase as usize]
+    }
+    /// logs the last complete window
+    pub fn report(&self){
+        for phase in Phase::ALL.iter(){
+            let summary = self.summary(*phase);
+            info!("profile {:?}: min {:?} avg {:?} max {:?}", phase.name(), summary.min, summary.avg, summary.max);
+        }
+    }
+
+    pub fn overlay(&self)->bool{
+        self.overlay
+    }
+    pub fn toggle_overlay(&mut self){
+        self.overlay = !self.overlay;
+    }
+    /// average of every phase in thousands of cycles, below the fps counter
+    pub fn draw_overlay<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error>{
+        for (row, phase) in Phase::ALL.iter().enumerate(){
+            let y = 10 + row as i32 * 8;
+            let label = &phase.name()[..1];
+            Text::new(label, Point::new(1, y))
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+            let avg:String<U12> = String::from(self.summary(*phase).avg / 1000);
+            Text::new(avg.as_str(), Point::new(9, y))
+                .into_styled(TextStyle::new(Font6x8, BinaryColor::On))
+                .draw(disp)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: CycleCounter> Probe for Profiler<C>{
+    fn measure<T, F: FnOnce()->T>(&mut self, phase:Phase, f:F)->T{
+        let start = self.counter.cycles();
+        let result = f();
+        let cycles = self.counter.cycles().wrapping_sub(start);
+        self.record(phase, cycles);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GamePool,
+        input::InputFrame,
+        rng::XorShift32,
+    };
+
+    /// counts up by 100 on every read
+    struct FakeCounter(u32);
+
+    impl CycleCounter for FakeCounter{
+        fn cycles(&mut self)->u32{
+            self.0 = self.0.wrapping_add(100);
+            self.0
+        }
+    }
+
+    #[test]
+    fn window_summary(){
+        let mut profiler = Profiler::new(FakeCounter(0));
+        for frame in 0..PROFILE_FRAMES{
+            profiler.record(Phase::Draw, 10 + frame);
+            let done = profiler.end_frame();
+            assert_eq!(done, frame == PROFILE_FRAMES - 1);
+        }
+        assert_eq!(profiler.summary(Phase::Draw), Summary{
+            min: 10,
+            avg: 10 + (PROFILE_FRAMES - 1) / 2,
+            max: 10 + PROFILE_FRAMES - 1,
+            samples: PROFILE_FRAMES,
+        });
+        assert_eq!(profiler.summary(Phase::Flush), Summary::default());
+    }
+
+    #[test]
+    fn game_tick_measures_every_phase(){
+        let mut profiler = Profiler::new(FakeCounter(u32::MAX - 150));
+        let mut game = GamePool::new(Size::new(64, 128));
+        let mut rng = XorShift32::new(1);
+        for _ in 0..PROFILE_FRAMES{
+            game.tick_probed(InputFrame::default(), &mut rng, &mut profiler);
+            profiler.end_frame();
+        }
+        for &phase in &[Phase::Spawn, Phase::Update, Phase::Collect]{
+            let summary = profiler.summary(phase);
+            assert_eq!(summary.samples, PROFILE_FRAMES);
+            assert_eq!((summary.min, summary.max), (100, 100));
+        }
+        assert_eq!(profiler.summary(Phase::Draw).samples, 0);
+    }
+}
@@ -0,0 +1,262 @@
+// Recording of a game: the seed it was started with and the buttons sampled every frame.
+// since the game core is deterministic that is enough to play the exact same game again.
+use crate::{
+    GamePool,
+    input::InputFrame,
+    objects::MAX_PLAYERS,
+    rng::XorShift32,
+    framebuffer::{WIDTH, HEIGHT},
+};
+
+use embedded_graphics::geometry::Size;
+
+use heapless::{
+    Vec,
+    consts::*,
+};
+
+/// identical input repeated for `len` frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run{
+    input: InputFrame,
+    len: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError{
+    /// recording has no space left for more input
+    Full,
+    /// buffer is too small to hold the recording
+    BufferTooSmall,
+    /// data doesn't start with `Recording::MAGIC`
+    BadMagic,
+    /// data is cut short or has invalid runs
+    Corrupt,
+}
+
+/// Seed and input stream of a single game, run length encoded.
+///
+/// serialized as `MAGIC`, seed as little endian u32, number of players and then a
+/// `(input bits, frames)` byte pair for every run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording{
+    seed: u32,
+    players: u8,
+    frames: u32,
+    runs: Vec<Run, U1024>,
+}
+
+/// how a replayed game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome{
+    /// score of all players together
+    pub score: i16,
+    /// number of frames played
+    pub frames: u32,
+    /// false if recording ran out before the game was over
+    pub game_over: bool,
+}
+
+/// feeds a recording back into a new game frame by frame
+pub struct Replay<'a>{
+    recording: &'a Recording,
+    game: GamePool,
+    rng: XorShift32,
+    run: usize,
+    run_pos: u8,
+    frame: u32,
+}
+
+impl Recording{
+    pub const MAGIC: [u8; 4] = *b"SWR2";
+    const HEADER_LEN: usize = 9;
+    /// size of the biggest recording when serialized
+    pub const MAX_SERIALIZED_LEN: usize = Self::HEADER_LEN + 1024 * 2;
+
+    pub fn new(seed:u32, players:u8)->Self{
+        Self{ seed, players, frames:0, runs: Vec::new() }
+    }
+    pub fn seed(&self)->u32{
+        self.seed
+    }
+    pub fn players(&self)->u8{
+        self.players
+    }
+    /// number of recorded frames
+    pub fn len(&self)->u32{
+        self.frames
+    }
+    pub fn is_empty(&self)->bool{
+        self.frames == 0
+    }
+
+    /// appends input of the next frame
+    pub fn record(&mut self, input:InputFrame)->Result<(), ReplayError>{
+        match self.runs.last_mut(){
+            Some(run) if run.input == input && run.len < u8::MAX => run.len += 1,
+            _ => self.runs.push(Run{ input, len:1 }).map_err(|_| ReplayError::Full)?,
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// every recorded frame in order
+    pub fn frames(&self)->impl Iterator<Item = InputFrame> + '_{
+        self.runs.iter().flat_map(|run| core::iter::repeat(run.input).take(run.len as usize))
+    }
+
+    /// number of bytes `write_to` needs
+    pub fn serialized_len(&self)->usize{
+        Self::HEADER_LEN + self.runs.len() * 2
+    }
+
+    /// serializes the recording into buf, returns the number of bytes written
+    pub fn write_to(&self, buf:&mut [u8])->Result<usize, ReplayError>{
+        let len = self.serialized_len();
+        if buf.len() < len{
+            return Err(ReplayError::BufferTooSmall);
+        }
+        buf[..4].copy_from_slice(&Self::MAGIC);
+        buf[4..8].copy_from_slice(&self.seed.to_le_bytes());
+        buf[8] = self.players;
+        for (index, run) in self.runs.iter().enumerate(){
+            let pos = Self::HEADER_LEN + index * 2;
+            buf[pos] = run.input.bits();
+            buf[pos + 1] = run.len;
+        }
+        Ok(len)
+    }
+
+    pub fn read_from(buf:&[u8])->Result<Self, ReplayError>{
+        if buf.len() < Self::HEADER_LEN{
+            return Err(ReplayError::Corrupt);
+        }
+        if buf[..4] != Self::MAGIC{
+            return Err(ReplayError::BadMagic);
+        }
+        let body = &buf[Self::HEADER_LEN..];
+        if body.len() % 2 != 0{
+            return Err(ReplayError::Corrupt);
+        }
+        if buf[8] == 0 || buf[8] > MAX_PLAYERS{
+            return Err(ReplayError::Corrupt);
+        }
+        let mut seed = [0; 4];
+        seed.copy_from_slice(&buf[4..8]);
+        let mut recording = Self::new(u32::from_le_bytes(seed), buf[8]);
+        for pair in body.chunks(2){
+            if pair[1] == 0 || InputFrame::from_bits(pair[0]).bits() != pair[0]{
+                return Err(ReplayError::Corrupt);
+            }
+            recording.runs.push(Run{ input: InputFrame::from_bits(pair[0]), len: pair[1] })
+                .map_err(|_| ReplayError::Full)?;
+            recording.frames += pair[1] as u32;
+        }
+        Ok(recording)
+    }
+}
+
+impl<'a> Replay<'a>{
+    pub fn new(recording:&'a Recording)->Self{
+        Self{
+            recording,
+            game: GamePool::with_players(Size::new(WIDTH as u32, HEIGHT as u32), recording.players()),
+            rng: XorShift32::new(recording.seed()),
+            run: 0,
+            run_pos: 0,
+            frame: 0,
+        }
+    }
+    pub fn game(&self)->&GamePool{
+        &self.game
+    }
+    /// number of frames played so far
+    pub fn frame(&self)->u32{
+        self.frame
+    }
+
+    /// plays the next recorded frame, returns false if there is nothing left to play
+    pub fn step(&mut self)->bool{
+        if self.game.is_ok(){
+            return false;
+        }
+        let run = match self.recording.runs.get(self.run){
+            Some(run) => *run,
+            None => return false,
+        };
+        self.game.tick(run.input, &mut self.rng);
+        self.frame += 1;
+        self.run_pos += 1;
+        if self.run_pos == run.len{
+            self.run += 1;
+            self.run_pos = 0;
+        }
+        true
+    }
+
+    /// plays the recording till the end or till game is over
+    pub fn run(mut self)->Outcome{
+        while self.step(){}
+        Outcome{
+            score: self.game.score(),
+            frames: self.frame,
+            game_over: self.game.is_ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// moves around and shoots every now and then
+    fn scripted_input(frame:u32)->InputFrame{
+        let left = (frame / 20) % 2 == 0;
+        InputFrame::new(left, !left, frame % 7 == 0, false)
+    }
+
+    #[test]
+    fn serialization_round_trip(){
+        let mut recording = Recording::new(1234, 2);
+        for frame in 0..600{
+            recording.record(scripted_input(frame)).unwrap();
+        }
+        let mut buf = [0u8; Recording::MAX_SERIALIZED_LEN];
+        let len = recording.write_to(&mut buf).unwrap();
+        assert_eq!(len, recording.serialized_len());
+        assert_eq!(Recording::read_from(&buf[..len]), Ok(recording));
+
+        assert_eq!(Recording::read_from(b"SWR1\0\0\0\0\x01"), Err(ReplayError::BadMagic));
+        assert_eq!(Recording::read_from(b"SWR2\0\0\0\0\x03"), Err(ReplayError::Corrupt));
+        assert_eq!(Recording::read_from(&buf[..len - 1]), Err(ReplayError::Corrupt));
+    }
+
+    #[test]
+    fn replay_matches_recorded_game(){
+        let mut recording = Recording::new(99, 1);
+        let mut game = GamePool::new(Size::new(WIDTH as u32, HEIGHT as u32));
+        let mut rng = XorShift32::new(recording.seed());
+        let mut frames = 0;
+        while frames < 2000 && !game.is_ok(){
+            let input = scripted_input(frames);
+            recording.record(input).unwrap();
+            game.tick(input, &mut rng);
+            frames += 1;
+        }
+
+        let outcome = Replay::new(&recording).run();
+        assert_eq!(outcome.frames, frames);
+        assert_eq!(outcome.score, game.score());
+        assert_eq!(outcome.game_over, game.is_ok());
+    }
+
+    /// seed 99 with `scripted_input` till the game was over, catches changes to the game that
+    /// would break old recordings. after an intended change of the game play update the outcome
+    #[test]
+    fn checked_in_recording(){
+        let recording = Recording::read_from(include_bytes!("../tests/replays/scripted_run.swr")).unwrap();
+        assert_eq!(recording.seed(), 99);
+        assert_eq!(recording.len(), 1237);
+        assert_eq!(Replay::new(&recording).run(), Outcome{ score:107, frames:1237, game_over:true });
+    }
+}
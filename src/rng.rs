@@ -0,0 +1,77 @@
+/// Source of random values used for spawning objects
+pub trait GameRng{
+    /// returns the next random value
+    fn next_u32(&mut self)->u32;
+
+    /// returns a random value in `0..upper`, upper of 0 always gives 0
+    fn next_below(&mut self, upper:u32)->u32{
+        if upper == 0{
+            return 0;
+        }
+        self.next_u32() % upper
+    }
+}
+
+/// xorshift32 pseudo random generator.
+///
+/// the whole game is reproducible from the seed it was started with,
+/// which is what the simulator, replays and tests rely on.
+#[derive(Debug, Clone)]
+pub struct XorShift32{
+    state:u32,
+}
+
+impl XorShift32{
+    // xorshift gets stuck on 0, so zero seed is replaced with this
+    const ZERO_SEED:u32 = 0x9e37_79b9;
+
+    pub fn new(seed:u32)->Self{
+        let state = if seed == 0 { Self::ZERO_SEED } else { seed };
+        Self{ state }
+    }
+}
+
+impl GameRng for XorShift32{
+    fn next_u32(&mut self)->u32{
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_sequence(){
+        let mut rng = XorShift32::new(1);
+        let values:[u32; 5] = [
+            rng.next_u32(), rng.next_u32(), rng.next_u32(), rng.next_u32(), rng.next_u32(),
+        ];
+        assert_eq!(values, [270_369, 67_634_689, 2_647_435_461, 307_599_695, 2_398_689_233]);
+    }
+
+    #[test]
+    fn zero_seed_doesnt_get_stuck(){
+        let mut rng = XorShift32::new(0);
+        let mut replaced = XorShift32::new(XorShift32::ZERO_SEED);
+        assert_eq!(rng.next_u32(), 1_359_758_873);
+        assert_eq!(replaced.next_u32(), 1_359_758_873);
+        for _ in 0..100{
+            assert_ne!(rng.next_u32(), 0);
+        }
+    }
+
+    #[test]
+    fn below_upper_bound(){
+        let mut rng = XorShift32::new(42);
+        assert_eq!(rng.next_below(0), 0);
+        for _ in 0..100{
+            assert!(rng.next_below(7) < 7);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Scenes of the game and the transitions between them, driven once per frame by the main loop.
+//
+//      Title --shoot--> Playing --pause--> Paused
+//        ^                 |  ^--pause------'
+//        |              player dies
+//        '--shoot---- GameOver
+use crate::{
+    GamePool,
+    draw_final_screen,
+    draw_pause,
+    draw_title,
+    input::InputFrame,
+    objects::{BLINK_FRAMES, GAME_OVER_FRAMES},
+    profiler::Probe,
+    replay::Recording,
+    rng::{GameRng, XorShift32},
+};
+
+use embedded_graphics::{
+    prelude::*,
+    pixelcolor::BinaryColor,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scene{
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Whole game: current scene, the running game and its recording.
+///
+/// every game is played with its own rng seeded at start, so that it can be replayed
+/// from its recording.
+#[derive(Debug)]
+pub struct Game{
+    scene: Scene,
+    // frames spent in current scene
+    scene_frames: u32,
+    size: Size,
+    pool: GamePool,
+    rng: XorShift32,
+    recording: Recording,
+}
+
+impl Game{
+    pub fn init<D: DrawTarget<BinaryColor>>(disp:&D)->Self{
+        Self::new(disp.size())
+    }
+
+    pub fn new(size:Size)->Self{
+        Self{
+            scene: Scene::Title,
+            scene_frames: 0,
+            size,
+            pool: GamePool::new(size),
+            rng: XorShift32::new(0),
+            recording: Recording::new(0, 1),
+        }
+    }
+    pub fn scene(&self)->Scene{
+        self.scene
+    }
+    pub fn pool(&self)->&GamePool{
+        &self.pool
+    }
+    pub fn pool_mut(&mut self)->&mut GamePool{
+        &mut self.pool
+    }
+    /// recording of the current or last game
+    pub fn recording(&self)->&Recording{
+        &self.recording
+    }
+
+    /// advances the game by one frame, `rng` is only used to seed new games
+    pub fn tick<R: GameRng>(&mut self, input:InputFrame, rng:&mut R){
+        self.tick_probed(input, rng, &mut ());
+    }
+
+    /// same as tick, with phases of the game measured by probe
+    pub fn tick_probed<R: GameRng, P: Probe>(&mut self, input:InputFrame, rng:&mut R, probe:&mut P){
+        self.scene_frames = self.scene_frames.saturating_add(1);
+        match self.scene{
+            Scene::Title => {
+                // whoever shoots first picks the mode
+                if input.player(1).shoot(){
+                    self.start(rng.next_u32(), 2);
+                } else if input.shoot(){
+                    self.start(rng.next_u32(), 1);
+                }
+            },
+            Scene::Playing => {
+                if input.pause(){
+                    self.set_scene(Scene::Paused);
+                    return;
+                }
+                if self.recording.record(input).is_err(){
+                    debug!("recording full, replay will be incomplete");
+                }
+                self.pool.tick_probed(input, &mut self.rng, probe);
+                if self.pool.is_ok(){
+                    self.set_scene(Scene::GameOver);
+                }
+            },
+            Scene::Paused => {
+                if input.pause(){
+                    self.set_scene(Scene::Playing);
+                }
+            },
+            Scene::GameOver => {
+                // ignore shots fired right before dying
+                if input.shoot() && self.scene_frames > GAME_OVER_FRAMES{
+                    self.set_scene(Scene::Title);
+                }
+            },
+        }
+    }
+
+    /// starts a new game for 1 or 2 players with given seed
+    pub fn start(&mut self, seed:u32, players:u8){
+        debug!("new game with seed {:?} for {:?} players", seed, players);
+        self.pool = GamePool::with_players(self.size, players);
+        self.rng = XorShift32::new(seed);
+        self.recording = Recording::new(seed, self.pool.players.len() as u8);
+        self.set_scene(Scene::Playing);
+    }
+
+    pub fn draw<D: DrawTarget<BinaryColor>>(&self, disp:&mut D)->Result<(), D::Error>{
+        let blink = (self.scene_frames / BLINK_FRAMES) % 2 == 1;
+        match self.scene{
+            Scene::Title => draw_title(blink, disp),
+            Scene::Playing => {
+                self.pool.draw(disp)?;
+                self.pool.draw_stats(disp)
+            },
+            Scene::Paused => {
+                self.pool.draw(disp)?;
+                self.pool.draw_stats(disp)?;
+                draw_pause(disp)
+            },
+            Scene::GameOver => draw_final_screen(&self.pool.scores(), blink, disp),
+        }
+    }
+
+    fn set_scene(&mut self, scene:Scene){
+        debug!("scene change");
+        self.scene = scene;
+        self.scene_frames = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Movable;
+
+    const SHOOT:InputFrame = InputFrame::from_bits(InputFrame::SHOOT);
+    const PAUSE:InputFrame = InputFrame::from_bits(InputFrame::PAUSE);
+    const NONE:InputFrame = InputFrame::from_bits(0);
+
+    #[test]
+    fn shoot_starts_game_from_title(){
+        let mut rng = XorShift32::new(1);
+        let mut game = Game::new(Size::new(64, 128));
+        game.tick(NONE, &mut rng);
+        assert_eq!(game.scene(), Scene::Title);
+        game.tick(SHOOT, &mut rng);
+        assert_eq!(game.scene(), Scene::Playing);
+        assert_eq!(game.recording().seed(), XorShift32::new(1).next_u32());
+        assert_eq!(game.pool().players.len(), 1);
+    }
+
+    #[test]
+    fn second_player_shoot_starts_two_player_game(){
+        let mut rng = XorShift32::new(1);
+        let mut game = Game::new(Size::new(64, 128));
+        game.tick(NONE.with_player_2(false, false, true), &mut rng);
+        assert_eq!(game.scene(), Scene::Playing);
+        assert_eq!(game.pool().players.len(), 2);
+        assert_eq!(game.recording().players(), 2);
+    }
+
+    #[test]
+    fn pause_freezes_game(){
+        let mut rng = XorShift32::new(1);
+        let mut game = Game::new(Size::new(64, 128));
+        game.start(5, 1);
+        game.tick(NONE, &mut rng);
+        game.tick(PAUSE, &mut rng);
+        assert_eq!(game.scene(), Scene::Paused);
+        let asteroid = game.pool().asteroids()[0].get_pos();
+        for _ in 0..10{
+            game.tick(NONE, &mut rng);
+        }
+        assert_eq!(game.pool().asteroids()[0].get_pos(), asteroid);
+        game.tick(PAUSE, &mut rng);
+        assert_eq!(game.scene(), Scene::Playing);
+        // paused frames are not part of the replay
+        assert_eq!(game.recording().len(), 1);
+    }
+
+    #[test]
+    fn game_over_returns_to_title(){
+        let mut rng = XorShift32::new(1);
+        let mut game = Game::new(Size::new(64, 128));
+        game.start(5, 1);
+        // nobody is steering, sooner or later something hits the player
+        let mut frames = 0;
+        while game.scene() == Scene::Playing{
+            game.tick(NONE, &mut rng);
+            frames += 1;
+            assert!(frames < 100_000, "player never died");
+        }
+        assert_eq!(game.scene(), Scene::GameOver);
+
+        game.tick(SHOOT, &mut rng);
+        assert_eq!(game.scene(), Scene::GameOver);
+        for _ in 0..GAME_OVER_FRAMES{
+            game.tick(NONE, &mut rng);
+        }
+        game.tick(SHOOT, &mut rng);
+        assert_eq!(game.scene(), Scene::Title);
+        game.tick(SHOOT, &mut rng);
+        assert_eq!(game.scene(), Scene::Playing);
+        assert_eq!(game.pool().score(), 0);
+    }
+}
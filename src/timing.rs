@@ -0,0 +1,179 @@
+// Fixed timestep scheduling: the game is ticked TICK_RATE times a second no matter how long
+// drawing takes, frames are drawn whenever there is time left, at most FPS_LIMIT a second.
+//
+// the main loop asks `FixedStep::poll` what to do next and sleeps (WFI on the board) when
+// the answer is `Step::Idle`.
+use crate::objects::{FPS_LIMIT, MAX_CATCH_UP, TICK_RATE};
+
+const SECOND_US: u32 = 1_000_000;
+
+/// Monotonic time source in microseconds, allowed to wrap around.
+pub trait Clock{
+    fn now_us(&mut self)->u32;
+}
+
+/// what the main loop should do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step{
+    /// advance the game by one tick
+    Tick,
+    /// draw the current state of the game
+    Draw,
+    /// nothing to do till next tick, sleep
+    Idle,
+}
+
+#[derive(Debug)]
+pub struct FixedStep{
+    tick_us: u32,
+    frame_us: u32,
+    last: u32,
+    // time not yet simulated
+    lag_us: u32,
+    last_frame: u32,
+    // game has ticked since last frame was drawn
+    dirty: bool,
+    // counted over the last second
+    window_start: u32,
+    frames: u16,
+    ticks: u16,
+    fps: u16,
+    tps: u16,
+}
+
+impl FixedStep{
+    pub fn new(now:u32)->Self{
+        Self::with_rates(TICK_RATE, FPS_LIMIT as u32, now)
+    }
+    pub fn with_rates(tick_rate:u32, fps_limit:u32, now:u32)->Self{
+        Self{
+            tick_us: SECOND_US / tick_rate.max(1),
+            frame_us: SECOND_US / fps_limit.max(1),
+            last: now,
+            lag_us: 0,
+            last_frame: now.wrapping_sub(SECOND_US),
+            dirty: false,
+            window_start: now,
+            frames: 0,
+            ticks: 0,
+            fps: 0,
+            tps: 0,
+        }
+    }
+
+    /// returns the next thing to do, ticks come first so that a slow display doesn't slow down
+    /// the game
+    pub fn poll(&mut self, now:u32)->Step{
+        // after a long stall, e.g. a debugger break, skip ahead instead of running many ticks
+        // in a row
+        let elapsed = now.wrapping_sub(self.last);
+        self.last = now;
+        self.lag_us = self.lag_us.saturating_add(elapsed).min(self.tick_us * MAX_CATCH_UP);
+        if now.wrapping_sub(self.window_start) >= SECOND_US{
+            self.fps = self.frames;
+            self.tps = self.ticks;
+            self.frames = 0;
+            self.ticks = 0;
+            self.window_start = now;
+        }
+
+        if self.lag_us >= self.tick_us{
+            self.lag_us -= self.tick_us;
+            self.ticks += 1;
+            self.dirty = true;
+            return Step::Tick;
+        }
+        if self.dirty && now.wrapping_sub(self.last_frame) >= self.frame_us{
+            self.dirty = false;
+            self.last_frame = now;
+            self.frames += 1;
+            return Step::Draw;
+        }
+        Step::Idle
+    }
+
+    /// frames drawn during the last second
+    pub fn fps(&self)->u16{
+        self.fps
+    }
+    /// ticks run during the last second, TICK_RATE unless game can't keep up
+    pub fn tps(&self)->u16{
+        self.tps
+    }
+    /// how far the game is behind the clock
+    pub fn lag_us(&self)->u32{
+        self.lag_us
+    }
+    /// time till the next tick is due
+    pub fn until_next_tick(&self)->u32{
+        self.tick_us.saturating_sub(self.lag_us)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// polls every `poll_us` and spends `draw_us` on every draw, returns (ticks, frames)
+    fn run(step:&mut FixedStep, now:&mut u32, until:u32, poll_us:u32, draw_us:u32)->(u32, u32){
+        let (mut ticks, mut frames) = (0, 0);
+        while *now < until{
+            match step.poll(*now){
+                Step::Tick => ticks += 1,
+                Step::Draw => {
+                    frames += 1;
+                    *now += draw_us;
+                },
+                Step::Idle => *now += poll_us,
+            }
+        }
+        (ticks, frames)
+    }
+
+    #[test]
+    fn tick_rate_doesnt_depend_on_drawing(){
+        for &draw_us in &[1_000, 20_000, 60_000]{
+            let mut now = 0;
+            let mut step = FixedStep::with_rates(30, 30, now);
+            let (mut ticks, frames) = run(&mut step, &mut now, 2 * SECOND_US, 100, draw_us);
+            // a draw running past the end leaves some ticks to be caught up with
+            while step.poll(now) == Step::Tick{
+                ticks += 1;
+            }
+            let due = now / (SECOND_US / 30);
+            assert!(ticks <= due && ticks + 1 >= due, "{} ticks in {}us with {}us draws", ticks, now, draw_us);
+            assert!(frames <= 60);
+        }
+    }
+
+    #[test]
+    fn frames_are_limited(){
+        let mut now = 0;
+        let mut step = FixedStep::with_rates(60, 20, now);
+        let (ticks, frames) = run(&mut step, &mut now, SECOND_US, 100, 0);
+        assert!((59..=60).contains(&ticks));
+        assert!((19..=20).contains(&frames));
+        run(&mut step, &mut now, SECOND_US + 200, 100, 0);
+        assert_eq!(step.fps() as u32, frames);
+        assert_eq!(step.tps() as u32, ticks);
+    }
+
+    #[test]
+    fn long_stall_doesnt_pile_up_ticks(){
+        let mut step = FixedStep::with_rates(30, 30, 0);
+        let mut ticks = 0;
+        while step.poll(5 * SECOND_US) == Step::Tick{
+            ticks += 1;
+        }
+        assert_eq!(ticks, MAX_CATCH_UP);
+        assert!(step.lag_us() < SECOND_US / 30);
+    }
+
+    #[test]
+    fn clock_may_wrap(){
+        let start = u32::MAX - 10_000;
+        let mut step = FixedStep::with_rates(100, 100, start);
+        assert_eq!(step.poll(start.wrapping_add(10_000)), Step::Tick);
+        assert_eq!(step.until_next_tick(), 10_000);
+    }
+}
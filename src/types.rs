@@ -3,7 +3,7 @@ use stm32f7xx_hal::{
     pac::I2C2,
     gpio::{
         Alternate, Input, PullUp,
-        gpiof::{ PF0, PF1, PF2, PF9, PF8 , PF6}
+        gpiof::{ PF0, PF1, PF2, PF9, PF8 , PF6, PF3, PF4, PF5}
     },
 
 };
@@ -14,3 +14,7 @@ pub type Right = PF8<Input<PullUp>>;
 pub type ButtonShoot = PF2<Input<PullUp>>;
 pub type Delay = stm32f7xx_hal::delay::Delay;
 pub type Pause = PF6<Input<PullUp>>;
+// second player
+pub type Left2 = PF4<Input<PullUp>>;
+pub type Right2 = PF5<Input<PullUp>>;
+pub type ButtonShoot2 = PF3<Input<PullUp>>;
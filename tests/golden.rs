@@ -0,0 +1,144 @@
+// Golden frame tests: renders the game into a framebuffer and compares it with the reference
+// bitmaps in tests/golden.
+//
+// run with `UPDATE_GOLDEN=1` to regenerate the references after an intended change in the
+// rendering, then check the new PBM files before commiting them. a missing reference fails the
+// test, new tests need a run with `UPDATE_GOLDEN=1` too. on mismatch the rendered frame is
+// written next to the reference as `<name>.actual.pbm`.
+
+use std::{
+    env,
+    fs,
+    path::PathBuf,
+};
+
+use embedded_graphics::{
+    prelude::*,
+    pixelcolor::BinaryColor,
+};
+
+use space_war::{
+    GamePool,
+    draw_final_screen,
+    draw_pause,
+    framebuffer::{FrameBuffer, PBM_HEADER, WIDTH, HEIGHT},
+    input::InputFrame,
+    rng::XorShift32,
+};
+
+fn golden_path(name:&str, suffix:&str)->PathBuf{
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}{}.pbm", name, suffix))
+}
+
+fn save(path:&PathBuf, frame:&FrameBuffer){
+    let mut data = PBM_HEADER.as_bytes().to_vec();
+    data.extend_from_slice(frame.as_bytes());
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+}
+
+fn load(path:&PathBuf)->Option<FrameBuffer>{
+    let data = fs::read(path).ok()?;
+    let raster = data.strip_prefix(PBM_HEADER.as_bytes())
+        .unwrap_or_else(|| panic!("{} is not a 64x128 binary PBM", path.display()));
+    FrameBuffer::from_bytes(raster)
+}
+
+/// compares the frame with reference `name`
+fn assert_golden(name:&str, frame:&FrameBuffer){
+    let path = golden_path(name, "");
+    if env::var_os("UPDATE_GOLDEN").is_some(){
+        eprintln!("writing golden frame {}", path.display());
+        save(&path, frame);
+        return;
+    }
+    let expected = load(&path).unwrap_or_else(|| panic!(
+        "reference {} is missing, run with UPDATE_GOLDEN=1 to create it", path.display()
+        ));
+    if &expected == frame{
+        return;
+    }
+    let mut diff = 0;
+    let mut first = None;
+    for y in 0..HEIGHT{
+        for x in 0..WIDTH{
+            if expected.get_pixel(x, y) != frame.get_pixel(x, y){
+                diff += 1;
+                first.get_or_insert((x, y));
+            }
+        }
+    }
+    let actual = golden_path(name, ".actual");
+    save(&actual, frame);
+    panic!(
+        "frame {} differs from reference in {} pixels, first at {:?}. rendered frame is in {}",
+        name, diff, first.unwrap(), actual.display()
+        );
+}
+
+/// plays `frames` frames with given input and renders the last one the way the board does
+fn render_game<F: Fn(u32)->InputFrame>(seed:u32, frames:u32, input:F)->FrameBuffer{
+    let mut disp = FrameBuffer::new();
+    let mut game = GamePool::init(&disp);
+    let mut rng = XorShift32::new(seed);
+    for frame in 0..frames{
+        game.tick(input(frame), &mut rng);
+    }
+    disp.clear(BinaryColor::Off).unwrap();
+    game.draw(&mut disp).unwrap();
+    game.draw_stats(&mut disp).unwrap();
+    disp
+}
+
+#[test]
+fn first_frame(){
+    let frame = render_game(42, 1, |_| InputFrame::default());
+    assert_golden("first_frame", &frame);
+}
+
+#[test]
+fn moving_and_shooting(){
+    let frame = render_game(7, 40, |frame|{
+        InputFrame::new(frame < 15, frame >= 25, frame % 10 == 0, false)
+    });
+    assert_golden("moving_and_shooting", &frame);
+}
+
+#[test]
+fn final_screen(){
+    for &(blink, name) in &[(false, "final_screen_game"), (true, "final_screen_over")]{
+        let mut disp = FrameBuffer::new();
+        draw_final_screen(&[123], blink, &mut disp).unwrap();
+        assert_golden(name, &disp);
+    }
+}
+
+#[test]
+fn two_players(){
+    let mut disp = FrameBuffer::new();
+    let mut game = GamePool::with_players(disp.size(), 2);
+    let mut rng = XorShift32::new(42);
+    for frame in 0..30{
+        let input = InputFrame::new(frame < 10, false, frame % 10 == 0, false)
+            .with_player_2(false, frame < 10, frame % 10 == 5);
+        game.tick(input, &mut rng);
+    }
+    disp.clear(BinaryColor::Off).unwrap();
+    game.draw(&mut disp).unwrap();
+    game.draw_stats(&mut disp).unwrap();
+    assert_golden("two_players", &disp);
+
+    disp.clear(BinaryColor::Off).unwrap();
+    draw_final_screen(&[12, 34], false, &mut disp).unwrap();
+    assert_golden("final_screen_two_players", &disp);
+}
+
+#[test]
+fn pause_overlay(){
+    let mut disp = render_game(42, 1, |_| InputFrame::default());
+    draw_pause(&mut disp).unwrap();
+    assert_golden("pause", &disp);
+}